@@ -0,0 +1,21 @@
+use clap::Parser;
+use polychromatic::Effect;
+use std::{error::Error, path::PathBuf};
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+#[command(propagate_version = true)]
+pub struct Cli {
+    #[arg(short, long)]
+    pub effect: PathBuf,
+}
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    let effect = Effect::load(&cli.effect)?;
+
+    effect.play_in_terminal()?;
+
+    Ok(())
+}
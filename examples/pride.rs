@@ -1,6 +1,8 @@
 use clap::Parser;
-use polychromatic::{Color, Device, Effect, Keyboard};
-use std::{error::Error, path::PathBuf, sync::LazyLock};
+use polychromatic::{
+    Color, DeltaThreshold, Device, Easing, EasingMode, Effect, FrameEncoding, Gradient, Keyboard,
+};
+use std::{error::Error, path::PathBuf, sync::LazyLock, time::Duration};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -13,7 +15,7 @@ pub struct Cli {
 }
 
 const FPS: u32 = 30;
-const FRAMES: u32 = 400;
+const DURATION: Duration = Duration::from_secs(13);
 
 const SCALE: f32 = 0.15;
 
@@ -37,36 +39,6 @@ static COLORS: LazyLock<Box<[Color]>> = LazyLock::new(|| {
     .collect()
 });
 
-fn get_color(percent: f32) -> Color {
-    let percent = percent % 1.0;
-
-    let index = (percent * COLORS.len() as f32) as usize;
-    let next_index = (index + 1) % COLORS.len();
-    let color = COLORS[index];
-    let next_color = COLORS[next_index];
-
-    fn lerp(v0: f32, v1: f32, t: f32) -> f32 {
-        (1.0 - t) * v0 + t * v1
-    }
-
-    fn simple_ease(x: f32) -> f32 {
-        if x > 0.5 {
-            1.0 - (2.0 * (1.0 - x)).powi(4) / 2.0
-        } else {
-            (2.0 * x).powi(4) / 2.0
-        }
-    }
-
-    let interpolation = percent % (1.0 / COLORS.len() as f32) * COLORS.len() as f32;
-    let interpolation = simple_ease(interpolation);
-
-    Color::new(
-        lerp(color.r, next_color.r, interpolation),
-        lerp(color.g, next_color.g, interpolation),
-        lerp(color.b, next_color.b, interpolation),
-    )
-}
-
 pub fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
 
@@ -75,19 +47,17 @@ pub fn main() -> Result<(), Box<dyn Error>> {
     effect.name = cli.output.file_stem().unwrap().to_str().unwrap().to_owned();
 
     effect.set_fps(FPS)?;
+    effect.encoding = FrameEncoding::Delta(DeltaThreshold::Perceptual(0.01));
 
-    let width = effect.width();
+    let mut gradient = Gradient::looping(COLORS.iter().copied());
+    gradient.set_easing(Easing::Quart(EasingMode::InOut));
 
-    let mut anim_percent = 0.0;
-    while anim_percent < 1.0 {
-        anim_percent += 1.0 / (FRAMES as f32);
+    let width = effect.width();
 
-        let frame = effect.new_frame();
-        frame.iter_mut().for_each(|(x, _y, color)| {
-            let percent = x as f32 / width as f32;
-            *color = get_color((percent * SCALE) + anim_percent);
-        });
-    }
+    effect.render_pixels(DURATION, |x, _y, time| {
+        let percent = x as f32 / width as f32;
+        gradient.sample((percent * SCALE) + time.phase)
+    });
 
     effect.save(&cli.output)?;
 
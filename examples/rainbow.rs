@@ -1,6 +1,6 @@
 use clap::Parser;
 use polychromatic::{Color, Device, Effect, Keyboard};
-use std::{error::Error, path::PathBuf};
+use std::{error::Error, path::PathBuf, time::Duration};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -13,7 +13,7 @@ pub struct Cli {
 }
 
 const FPS: u32 = 30;
-const FRAMES: u32 = 60;
+const DURATION: Duration = Duration::from_secs(2);
 
 pub fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
@@ -27,17 +27,11 @@ pub fn main() -> Result<(), Box<dyn Error>> {
 
     let width = effect.width();
 
-    let mut hue_rot = 0.0;
-    while hue_rot < 360.0 {
-        hue_rot += 360.0 / (FRAMES as f32);
-
-        let frame = effect.new_frame();
-        frame.iter_mut().for_each(|(x, y, color)| {
-            let hue = x as f32 / width as f32;
-            let hue = if y % 2 == 0 { hue } else { -hue };
-            *color = Color::from_hsl(hue * 360.0 + hue_rot, 1.0, 0.5);
-        });
-    }
+    effect.render_pixels(DURATION, |x, y, time| {
+        let hue = x as f32 / width as f32;
+        let hue = if y % 2 == 0 { hue } else { -hue };
+        Color::from_hsl((hue + time.phase) * 360.0, 1.0, 0.5)
+    });
 
     effect.save(&cli.output)?;
 
@@ -0,0 +1,208 @@
+//! Sub-key precision drawing on an [`EffectMatrix`]. Key `(x, y)` is centered on `(x, y)` and
+//! covers half a key in each direction.
+
+use crate::{Color, EffectMatrix, Rgba};
+
+/// Values that translucent colors can be composited onto
+pub trait Composite {
+    /// Composite `source` on top of `self`
+    fn composite(&self, source: &Rgba) -> Self;
+}
+
+impl Composite for Color {
+    fn composite(&self, source: &Rgba) -> Self {
+        source.over_color(self)
+    }
+}
+
+impl Composite for Rgba {
+    fn composite(&self, source: &Rgba) -> Self {
+        source.over(self)
+    }
+}
+
+/// How partially covered keys are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Antialias {
+    /// Keys are either fully drawn or untouched, depending on their center
+    None,
+    /// Estimate the covered area of each key from the distance to the shape edge
+    #[default]
+    Coverage,
+    /// Average `n` by `n` samples in each key, `n` is at most [`MAX_SUPERSAMPLE`]
+    Supersample(u32),
+}
+
+/// Largest number of samples along each axis of a key with [`Antialias::Supersample`]
+pub const MAX_SUPERSAMPLE: u32 = 16;
+
+impl Antialias {
+    /// How much of the key at `x`, `y` is inside the shape described by the signed distance
+    /// function `sdf`, negative inside the shape
+    pub fn coverage<F: Fn(f32, f32) -> f32>(&self, sdf: &F, x: f32, y: f32) -> f32 {
+        match self {
+            Self::None if sdf(x, y) <= 0.0 => 1.0,
+            Self::None => 0.0,
+            Self::Coverage => (0.5 - sdf(x, y)).clamp(0.0, 1.0),
+            Self::Supersample(0) => Self::None.coverage(sdf, x, y),
+            Self::Supersample(n) => {
+                let n = (*n).min(MAX_SUPERSAMPLE);
+                let step = 1.0 / n as f32;
+                let inside = (0..n)
+                    .flat_map(|sy| (0..n).map(move |sx| (sx, sy)))
+                    .filter(|(sx, sy)| {
+                        sdf(
+                            x - 0.5 + (*sx as f32 + 0.5) * step,
+                            y - 0.5 + (*sy as f32 + 0.5) * step,
+                        ) <= 0.0
+                    })
+                    .count();
+                inside as f32 / (n * n) as f32
+            }
+        }
+    }
+}
+
+impl<T: Composite> EffectMatrix<T> {
+    /// Draw `color` on every key, faded by how much of it is covered
+    pub fn draw_coverage<F: FnMut(u32, u32) -> f32>(&mut self, color: Rgba, mut coverage: F) {
+        self.iter_mut().for_each(|(x, y, key)| {
+            let coverage = coverage(x, y).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                *key = key.composite(&Rgba {
+                    a: color.a * coverage,
+                    ..color
+                });
+            }
+        });
+    }
+
+    /// Draw the shape described by the signed distance function `sdf`
+    pub fn draw_sdf<F: Fn(f32, f32) -> f32>(&mut self, sdf: F, color: Rgba, antialias: Antialias) {
+        self.draw_coverage(color, |x, y| antialias.coverage(&sdf, x as f32, y as f32));
+    }
+
+    /// Draw a key sized square at a sub-key position, spread over the keys it overlaps
+    pub fn draw_point(&mut self, (px, py): (f32, f32), color: Rgba, antialias: Antialias) {
+        match antialias {
+            // Exact overlap of two unit squares.
+            Antialias::Coverage => self.draw_coverage(color, |x, y| {
+                (1.0 - (x as f32 - px).abs()).max(0.0) * (1.0 - (y as f32 - py).abs()).max(0.0)
+            }),
+            _ => self.draw_sdf(
+                |x, y| (x - px).abs().max((y - py).abs()) - 0.5,
+                color,
+                antialias,
+            ),
+        }
+    }
+
+    /// Draw a line `width` keys thick with round ends
+    pub fn draw_line(
+        &mut self,
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        color: Rgba,
+        antialias: Antialias,
+    ) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let length_squared = dx * dx + dy * dy;
+        self.draw_sdf(
+            |x, y| {
+                let t = if length_squared > 0.0 {
+                    (((x - from.0) * dx + (y - from.1) * dy) / length_squared).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (x - from.0 - t * dx).hypot(y - from.1 - t * dy) - width / 2.0
+            },
+            color,
+            antialias,
+        );
+    }
+
+    /// Draw a filled circle
+    pub fn draw_circle(
+        &mut self,
+        center: (f32, f32),
+        radius: f32,
+        color: Rgba,
+        antialias: Antialias,
+    ) {
+        self.draw_sdf(
+            |x, y| (x - center.0).hypot(y - center.1) - radius,
+            color,
+            antialias,
+        );
+    }
+
+    /// Draw the outline of a circle `width` keys thick
+    pub fn draw_ring(
+        &mut self,
+        center: (f32, f32),
+        radius: f32,
+        width: f32,
+        color: Rgba,
+        antialias: Antialias,
+    ) {
+        self.draw_sdf(
+            |x, y| ((x - center.0).hypot(y - center.1) - radius).abs() - width / 2.0,
+            color,
+            antialias,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Antialias, Color, EffectMatrix, MAX_SUPERSAMPLE};
+
+    #[test]
+    fn test_point() {
+        let white = Color::new(1.0, 1.0, 1.0).into();
+        let mut matrix = EffectMatrix::<Color>::new(4, 1);
+        matrix.draw_point((1.3, 0.0), white, Antialias::Coverage);
+        let brightness = matrix.values().iter().map(|c| c.r).collect::<Vec<_>>();
+        assert!((brightness[1] - 0.7).abs() < 1e-5);
+        assert!((brightness[2] - 0.3).abs() < 1e-5);
+        assert_eq!(brightness[0], 0.0);
+        assert_eq!(brightness[3], 0.0);
+
+        matrix.clear();
+        matrix.draw_point((1.3, 0.0), white, Antialias::Supersample(10));
+        assert!((matrix.get(2, 0).unwrap().r - 0.3).abs() < 1e-5);
+        let sdf = |x: f32, _| x - 1.3;
+        assert_eq!(
+            Antialias::Supersample(u32::MAX).coverage(&sdf, 1.0, 0.0),
+            Antialias::Supersample(MAX_SUPERSAMPLE).coverage(&sdf, 1.0, 0.0)
+        );
+
+        matrix.clear();
+        matrix.draw_point((1.3, 0.0), white, Antialias::None);
+        assert_eq!(matrix.get(1, 0).unwrap().r, 1.0);
+        assert_eq!(matrix.get(2, 0).unwrap().r, 0.0);
+    }
+
+    #[test]
+    fn test_shapes() {
+        let white = Color::new(1.0, 1.0, 1.0).into();
+        let mut matrix = EffectMatrix::<Color>::new(9, 9);
+        matrix.draw_circle((4.0, 4.0), 2.5, white, Antialias::Supersample(4));
+        assert_eq!(matrix.get(4, 4).unwrap().r, 1.0);
+        assert_eq!(matrix.get(0, 0).unwrap().r, 0.0);
+        let edge = matrix.get(6, 6).unwrap().r;
+        assert!(edge > 0.0 && edge < 1.0);
+
+        let mut matrix = EffectMatrix::<Color>::new(5, 3);
+        matrix.draw_line(
+            (0.0, 1.0),
+            (4.0, 1.0),
+            1.0,
+            Color::new(1.0, 0.0, 0.0).with_alpha(0.5),
+            Antialias::Coverage,
+        );
+        assert_eq!(matrix.get(2, 1), Some(&Color::new(0.5, 0.0, 0.0)));
+        assert_eq!(matrix.get(2, 0), Some(&Color::new(0.0, 0.0, 0.0)));
+    }
+}
@@ -0,0 +1,194 @@
+//! Blend modes, https://www.w3.org/TR/compositing-1/#blending
+
+use crate::{Color, EffectMatrix};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Source replaces the backdrop
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    /// Sum, clamped to white
+    Add,
+    /// Backdrop minus source, clamped to black
+    Subtract,
+    Difference,
+    Lighten,
+    Darken,
+    /// Hue of the source with the saturation and luminosity of the backdrop
+    Hue,
+    /// Saturation of the source with the hue and luminosity of the backdrop
+    Saturation,
+    /// Hue and saturation of the source with the luminosity of the backdrop
+    Color,
+    /// Luminosity of the source with the hue and saturation of the backdrop
+    Luminosity,
+}
+
+impl BlendMode {
+    /// Blend `source` on top of `backdrop`
+    pub fn blend(&self, backdrop: &Color, source: &Color) -> Color {
+        let (b, s) = (backdrop.clamp(), source.clamp());
+        let separable = |f: fn(f32, f32) -> f32| Color::new(f(b.r, s.r), f(b.g, s.g), f(b.b, s.b));
+        match self {
+            Self::Normal => s,
+            Self::Multiply => b * s,
+            Self::Screen => separable(screen),
+            Self::Overlay => separable(|b, s| hard_light(s, b)),
+            Self::Add => (b + s).clamp(),
+            Self::Subtract => (b - s).clamp(),
+            Self::Difference => separable(|b, s| (b - s).abs()),
+            Self::Lighten => separable(f32::max),
+            Self::Darken => separable(f32::min),
+            Self::Hue => set_lum(set_sat(s, sat(b)), lum(b)),
+            Self::Saturation => set_lum(set_sat(b, sat(s)), lum(b)),
+            Self::Color => set_lum(s, lum(b)),
+            Self::Luminosity => set_lum(b, lum(s)),
+        }
+    }
+
+    /// Blend `source` on top of `backdrop`, then fade the result in by `opacity`
+    pub fn blend_with_opacity(&self, backdrop: &Color, source: &Color, opacity: f32) -> Color {
+        backdrop.lerp(&self.blend(backdrop, source), opacity.clamp(0.0, 1.0))
+    }
+}
+
+fn screen(b: f32, s: f32) -> f32 {
+    b + s - b * s
+}
+
+fn hard_light(b: f32, s: f32) -> f32 {
+    if s <= 0.5 {
+        b * 2.0 * s
+    } else {
+        screen(b, 2.0 * s - 1.0)
+    }
+}
+
+fn lum(c: Color) -> f32 {
+    0.3 * c.r + 0.59 * c.g + 0.11 * c.b
+}
+
+fn clip_color(c: Color) -> Color {
+    let l = lum(c);
+    let n = c.r.min(c.g).min(c.b);
+    let x = c.r.max(c.g).max(c.b);
+    let mut c = c;
+    if n < 0.0 {
+        c = Color::new(l, l, l) + (c - Color::new(l, l, l)) * (l / (l - n));
+    }
+    if x > 1.0 {
+        c = Color::new(l, l, l) + (c - Color::new(l, l, l)) * ((1.0 - l) / (x - l));
+    }
+    c
+}
+
+fn set_lum(c: Color, l: f32) -> Color {
+    let d = l - lum(c);
+    clip_color(c + Color::new(d, d, d))
+}
+
+fn sat(c: Color) -> f32 {
+    c.r.max(c.g).max(c.b) - c.r.min(c.g).min(c.b)
+}
+
+fn set_sat(c: Color, s: f32) -> Color {
+    let mut components = [c.r, c.g, c.b];
+    let mut order = [0, 1, 2];
+    order.sort_by(|a, b| components[*a].total_cmp(&components[*b]));
+    let [min, mid, max] = order;
+    if components[max] > components[min] {
+        components[mid] =
+            (components[mid] - components[min]) * s / (components[max] - components[min]);
+        components[max] = s;
+    } else {
+        components[mid] = 0.0;
+        components[max] = 0.0;
+    }
+    components[min] = 0.0;
+    Color::new(components[0], components[1], components[2])
+}
+
+impl EffectMatrix {
+    /// Replace every color with the result of `f`
+    pub fn map<F: FnMut(u32, u32, Color) -> Color>(&mut self, mut f: F) {
+        self.iter_mut()
+            .for_each(|(x, y, color)| *color = f(x, y, *color));
+    }
+
+    /// Blend `source` on top of this matrix, keys outside of either matrix are left unchanged
+    pub fn blend(&mut self, source: &EffectMatrix, mode: BlendMode, opacity: f32) {
+        self.map(|x, y, color| match source.get(x, y) {
+            Some(source) => mode.blend_with_opacity(&color, source, opacity),
+            None => color,
+        });
+    }
+
+    /// Blend `source` on top of every key
+    pub fn blend_color(&mut self, source: &Color, mode: BlendMode, opacity: f32) {
+        self.map(|_, _, color| mode.blend_with_opacity(&color, source, opacity));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{BlendMode, Color};
+
+    #[test]
+    fn test_blend() {
+        let backdrop = Color::new(0.5, 0.25, 1.0);
+        let source = Color::new(0.5, 1.0, 0.0);
+        assert_eq!(BlendMode::Normal.blend(&backdrop, &source), source);
+        assert_eq!(
+            BlendMode::Multiply.blend(&backdrop, &source),
+            Color::new(0.25, 0.25, 0.0)
+        );
+        assert_eq!(
+            BlendMode::Screen.blend(&backdrop, &source),
+            Color::new(0.75, 1.0, 1.0)
+        );
+        assert_eq!(
+            BlendMode::Add.blend(&backdrop, &source),
+            Color::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            BlendMode::Subtract.blend(&backdrop, &source),
+            Color::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            BlendMode::Difference.blend(&backdrop, &source),
+            Color::new(0.0, 0.75, 1.0)
+        );
+        assert_eq!(
+            BlendMode::Lighten.blend(&backdrop, &source),
+            Color::new(0.5, 1.0, 1.0)
+        );
+        assert_eq!(
+            BlendMode::Darken.blend(&backdrop, &source),
+            Color::new(0.5, 0.25, 0.0)
+        );
+        assert_eq!(
+            BlendMode::Overlay.blend(&backdrop, &source),
+            Color::new(0.5, 0.5, 1.0)
+        );
+
+        // Luminosity of a gray onto a color keeps the hue.
+        let red = Color::new(1.0, 0.0, 0.0);
+        let gray = Color::new(0.2, 0.2, 0.2);
+        let result = BlendMode::Luminosity.blend(&red, &gray);
+        assert!(result.r > result.g && result.g == result.b);
+        assert_eq!(BlendMode::Hue.blend(&gray, &red), gray);
+        assert!(
+            BlendMode::Color
+                .blend(&gray, &red)
+                .delta_e(&BlendMode::Luminosity.blend(&red, &gray))
+                < 1e-3
+        );
+        assert_eq!(
+            BlendMode::Normal.blend_with_opacity(&backdrop, &source, 0.5),
+            Color::new(0.5, 0.625, 0.5)
+        );
+    }
+}
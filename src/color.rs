@@ -1,3 +1,12 @@
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize, de::Error};
+
+use crate::{BlendMode, ColorSpace};
+
+/// CIE XYZ of the D65 white point
+const D65: (f32, f32, f32) = (0.95047, 1.0, 1.08883);
+
 /// Each component is stored as f32 in a normalized range
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Color {
@@ -33,6 +42,156 @@ impl Color {
         Self::new(r + m, g + m, b + m)
     }
 
+    /// Returns `(hue, saturation, lightness)`, hue is in degrees
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let c = max - min;
+        let l = (max + min) / 2.0;
+
+        if c == 0.0 {
+            return (0.0, 0.0, l);
+        }
+
+        let h = if max == self.r {
+            ((self.g - self.b) / c).rem_euclid(6.0)
+        } else if max == self.g {
+            (self.b - self.r) / c + 2.0
+        } else {
+            (self.r - self.g) / c + 4.0
+        };
+        let s = c / (1.0 - (2.0 * l - 1.0).abs());
+
+        (h * 60.0, s, l)
+    }
+
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        let s = f32::clamp(s, 0.0, 1.0);
+        let v = f32::clamp(v, 0.0, 1.0);
+        let l = v * (1.0 - s / 2.0);
+        let s = if l == 0.0 || l == 1.0 {
+            0.0
+        } else {
+            (v - l) / l.min(1.0 - l)
+        };
+        Self::from_hsl(h, s, l)
+    }
+
+    /// Returns `(hue, saturation, value)`, hue is in degrees
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (h, s, l) = self.to_hsl();
+        let v = l + s * l.min(1.0 - l);
+        let s = if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+        (h, s, v)
+    }
+
+    /// Convert from linear light sRGB components
+    pub fn from_linear(r: f32, g: f32, b: f32) -> Self {
+        fn encode(c: f32) -> f32 {
+            if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        }
+        Self::new(encode(r), encode(g), encode(b))
+    }
+
+    /// Returns linear light sRGB components
+    pub fn to_linear(&self) -> (f32, f32, f32) {
+        fn decode(c: f32) -> f32 {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        }
+        (decode(self.r), decode(self.g), decode(self.b))
+    }
+
+    /// Convert from OKLab, https://bottosson.github.io/posts/oklab/
+    pub fn from_oklab(l: f32, a: f32, b: f32) -> Self {
+        let l_ = (l + 0.39633778 * a + 0.21580376 * b).powi(3);
+        let m_ = (l - 0.105561346 * a - 0.06385417 * b).powi(3);
+        let s_ = (l - 0.08948418 * a - 1.2914855 * b).powi(3);
+        Self::from_linear(
+            4.0767417 * l_ - 3.3077116 * m_ + 0.23096994 * s_,
+            -1.268438 * l_ + 2.6097574 * m_ - 0.34131938 * s_,
+            -0.0041960863 * l_ - 0.7034186 * m_ + 1.7076147 * s_,
+        )
+    }
+
+    /// Convert from OKLCH, the polar form of OKLab, hue is in degrees
+    pub fn from_oklch(l: f32, c: f32, h: f32) -> Self {
+        let (sin, cos) = h.to_radians().sin_cos();
+        Self::from_oklab(l, c * cos, c * sin)
+    }
+
+    /// Returns `(lightness, chroma, hue)` in OKLCH, hue is in degrees
+    pub fn to_oklch(&self) -> (f32, f32, f32) {
+        let (l, a, b) = self.to_oklab();
+        (l, a.hypot(b), b.atan2(a).to_degrees().rem_euclid(360.0))
+    }
+
+    /// Convert from CIE L\*a\*b\* with a D65 white point, lightness is in `0.0..=100.0`
+    pub fn from_lab(l: f32, a: f32, b: f32) -> Self {
+        fn f_inv(t: f32) -> f32 {
+            const DELTA: f32 = 6.0 / 29.0;
+            if t > DELTA {
+                t.powi(3)
+            } else {
+                3.0 * DELTA * DELTA * (t - 4.0 / 29.0)
+            }
+        }
+        let fy = (l + 16.0) / 116.0;
+        let x = D65.0 * f_inv(fy + a / 500.0);
+        let y = D65.1 * f_inv(fy);
+        let z = D65.2 * f_inv(fy - b / 200.0);
+        Self::from_linear(
+            3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+            -0.969266 * x + 1.8760108 * y + 0.041556 * z,
+            0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+        )
+    }
+
+    /// Returns `(lightness, a, b)` in CIE L\*a\*b\* with a D65 white point
+    pub fn to_lab(&self) -> (f32, f32, f32) {
+        fn f(t: f32) -> f32 {
+            const DELTA: f32 = 6.0 / 29.0;
+            if t > DELTA.powi(3) {
+                t.cbrt()
+            } else {
+                t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+            }
+        }
+        let (r, g, b) = self.to_linear();
+        let x = f((0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / D65.0);
+        let y = f((0.2126729 * r + 0.7151522 * g + 0.072175 * b) / D65.1);
+        let z = f((0.0193339 * r + 0.119192 * g + 0.9503041 * b) / D65.2);
+        (116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z))
+    }
+
+    /// Perceptual difference between two colors, the euclidean distance in OKLab. Around `0.02`
+    /// is barely noticeable.
+    pub fn delta_e(&self, other: &Color) -> f32 {
+        let (l0, a0, b0) = self.to_oklab();
+        let (l1, a1, b1) = other.to_oklab();
+        ((l0 - l1).powi(2) + (a0 - a1).powi(2) + (b0 - b1).powi(2)).sqrt()
+    }
+
+    /// Returns `(lightness, a, b)` in OKLab
+    pub fn to_oklab(&self) -> (f32, f32, f32) {
+        let (r, g, b) = self.to_linear();
+        let l_ = (0.41222147 * r + 0.53633254 * g + 0.051445993 * b).cbrt();
+        let m_ = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+        let s_ = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+        (
+            0.21045426 * l_ + 0.7936178 * m_ - 0.004072047 * s_,
+            1.9779985 * l_ - 2.4285922 * m_ + 0.4505937 * s_,
+            0.025904037 * l_ + 0.78277177 * m_ - 0.80867577 * s_,
+        )
+    }
+
     pub const fn to_quantized(&self) -> (u8, u8, u8) {
         (
             f32::clamp(self.r * 255.0, 0.0, 255.0) as u8,
@@ -50,34 +209,225 @@ impl Color {
         (r == 0) && (g == 0) && (b == 0)
     }
 
+    /// Linearly interpolate in sRGB from `self` at `t = 0.0` to `other` at `t = 1.0`
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        *self + (*other - *self) * t
+    }
+
+    /// Interpolate from `self` at `t = 0.0` to `other` at `t = 1.0` in `space`
+    pub fn mix(&self, other: &Color, t: f32, space: ColorSpace) -> Color {
+        space.interpolate(self, other, t)
+    }
+
+    /// Clamp each component to `0.0..=1.0`
+    pub fn clamp(&self) -> Color {
+        Color::new(
+            self.r.clamp(0.0, 1.0),
+            self.g.clamp(0.0, 1.0),
+            self.b.clamp(0.0, 1.0),
+        )
+    }
+
+    /// Blend `source` on top of `self`
+    pub fn blend(&self, source: &Color, mode: BlendMode) -> Color {
+        mode.blend(self, source)
+    }
+
+    /// Euclidean distance between two colors
+    pub fn distance(&self, other: &Color) -> f32 {
+        ((self.r - other.r).powi(2) + (self.g - other.g).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+
     pub fn to_hex(&self) -> String {
         let (r, g, b) = self.to_quantized();
         format!("#{:02X}{:02X}{:02X}", r, g, b)
     }
 
+    /// Parses `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`, alpha is discarded, use
+    /// [`Rgba::from_hex`] to keep it
     pub fn from_hex(hex: &str) -> Option<Color> {
+        Rgba::from_hex(hex).map(|rgba| rgba.color())
+    }
+
+    pub const fn with_alpha(&self, a: f32) -> Rgba {
+        Rgba::new(self.r, self.g, self.b, a)
+    }
+}
+
+/// [`Color`] with straight (not premultiplied) alpha
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Default for Rgba {
+    /// Fully transparent
+    fn default() -> Self {
+        Self::new(0.0, 0.0, 0.0, 0.0)
+    }
+}
+
+impl From<Color> for Rgba {
+    fn from(color: Color) -> Self {
+        color.with_alpha(1.0)
+    }
+}
+
+impl Rgba {
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn color(&self) -> Color {
+        Color::new(self.r, self.g, self.b)
+    }
+
+    pub const fn to_quantized(&self) -> (u8, u8, u8, u8) {
+        let (r, g, b) = self.color().to_quantized();
+        (r, g, b, f32::clamp(self.a * 255.0, 0.0, 255.0) as u8)
+    }
+
+    pub const fn from_quantized(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color::from_quantized(r, g, b).with_alpha(a as f32 / 255.0)
+    }
+
+    pub fn to_hex(&self) -> String {
+        let (r, g, b, a) = self.to_quantized();
+        format!("#{:02X}{:02X}{:02X}{:02X}", r, g, b, a)
+    }
+
+    /// Parses `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`, alpha defaults to opaque
+    pub fn from_hex(hex: &str) -> Option<Rgba> {
         let hex = hex.strip_prefix('#').unwrap_or(hex);
         match hex.chars().collect::<Box<[char]>>().as_ref() {
-            [c_r, c_g, c_b] | [c_r, c_g, c_b, _] => Some(Color::new(
+            [c_r, c_g, c_b] => Some(Rgba::from_hex(&format!("{c_r}{c_g}{c_b}F"))?),
+            [c_r, c_g, c_b, c_a] => Some(Rgba::new(
                 (c_r.to_digit(16)? as f32) / 15.0,
                 (c_g.to_digit(16)? as f32) / 15.0,
                 (c_b.to_digit(16)? as f32) / 15.0,
+                (c_a.to_digit(16)? as f32) / 15.0,
+            )),
+            [c1_r, c2_r, c1_g, c2_g, c1_b, c2_b] => Some(Rgba::from_hex(&format!(
+                "{c1_r}{c2_r}{c1_g}{c2_g}{c1_b}{c2_b}FF"
+            ))?),
+            [c1_r, c2_r, c1_g, c2_g, c1_b, c2_b, c1_a, c2_a] => Some(Rgba::new(
+                (((c1_r.to_digit(16)? << 4) | c2_r.to_digit(16)?) as f32) / 255.0,
+                (((c1_g.to_digit(16)? << 4) | c2_g.to_digit(16)?) as f32) / 255.0,
+                (((c1_b.to_digit(16)? << 4) | c2_b.to_digit(16)?) as f32) / 255.0,
+                (((c1_a.to_digit(16)? << 4) | c2_a.to_digit(16)?) as f32) / 255.0,
             )),
-            [c1_r, c2_r, c1_g, c2_g, c1_b, c2_b] | [c1_r, c2_r, c1_g, c2_g, c1_b, c2_b, _, _] => {
-                Some(Color::new(
-                    (((c1_r.to_digit(16)? << 4) | c2_r.to_digit(16)?) as f32) / 255.0,
-                    (((c1_g.to_digit(16)? << 4) | c2_g.to_digit(16)?) as f32) / 255.0,
-                    (((c1_b.to_digit(16)? << 4) | c2_b.to_digit(16)?) as f32) / 255.0,
-                ))
-            }
             _ => None,
         }
     }
+
+    /// Porter-Duff "over", composite `self` on top of `backdrop`
+    pub fn over(&self, backdrop: &Rgba) -> Rgba {
+        let a = self.a + backdrop.a * (1.0 - self.a);
+        if a <= 0.0 {
+            return Rgba::default();
+        }
+        let color =
+            (self.color() * self.a + backdrop.color() * backdrop.a * (1.0 - self.a)) * (1.0 / a);
+        color.with_alpha(a)
+    }
+
+    /// Composite `self` on top of an opaque `backdrop`
+    pub fn over_color(&self, backdrop: &Color) -> Color {
+        backdrop.lerp(&self.color(), self.a.clamp(0.0, 1.0))
+    }
+}
+
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, rhs: Color) -> Color {
+        Color::new(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b)
+    }
+}
+
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, rhs: Color) -> Color {
+        Color::new(self.r - rhs.r, self.g - rhs.g, self.b - rhs.b)
+    }
+}
+
+impl Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f32) -> Color {
+        Color::new(self.r * rhs, self.g * rhs, self.b * rhs)
+    }
+}
+
+impl Mul<Color> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Color {
+        Color::new(self.r * rhs.r, self.g * rhs.g, self.b * rhs.b)
+    }
+}
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, rhs: Color) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Color {
+    fn sub_assign(&mut self, rhs: Color) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<f32> for Color {
+    fn mul_assign(&mut self, rhs: f32) {
+        *self = *self * rhs;
+    }
+}
+
+impl MulAssign<Color> for Color {
+    fn mul_assign(&mut self, rhs: Color) {
+        *self = *self * rhs;
+    }
+}
+
+/// Serialized as a `#RRGGBB` hex string
+impl Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Color::from_hex(&hex).ok_or_else(|| D::Error::custom(format!("Invalid color {hex:?}")))
+    }
+}
+
+/// Serialized as a `#RRGGBBAA` hex string
+impl Serialize for Rgba {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgba {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Rgba::from_hex(&hex).ok_or_else(|| D::Error::custom(format!("Invalid color {hex:?}")))
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::Color;
+    use crate::{Color, Rgba};
 
     #[test]
     fn test() {
@@ -89,4 +439,94 @@ mod test {
         test_hex(255, 127, 0);
         test_hex(69, 42, 127);
     }
+
+    #[test]
+    fn test_color_spaces() {
+        fn assert_close(a: (f32, f32, f32), b: (f32, f32, f32), epsilon: f32) {
+            assert!(
+                (a.0 - b.0).abs() < epsilon
+                    && (a.1 - b.1).abs() < epsilon
+                    && (a.2 - b.2).abs() < epsilon,
+                "{a:?} != {b:?}"
+            );
+        }
+
+        let colors = [
+            (255, 0, 0),
+            (0, 255, 0),
+            (0, 0, 255),
+            (255, 255, 255),
+            (0, 0, 0),
+            (128, 128, 128),
+            (255, 127, 0),
+            (69, 42, 127),
+            (3, 200, 180),
+        ];
+        for (r, g, b) in colors {
+            let color = Color::from_quantized(r, g, b);
+            let rgb = (color.r, color.g, color.b);
+            let round_trip = |color: Color| (color.r, color.g, color.b);
+
+            let (h, s, l) = color.to_hsl();
+            assert_close(round_trip(Color::from_hsl(h, s, l)), rgb, 1e-4);
+            let (h, s, v) = color.to_hsv();
+            assert_close(round_trip(Color::from_hsv(h, s, v)), rgb, 1e-4);
+            let (r, g, b) = color.to_linear();
+            assert_close(round_trip(Color::from_linear(r, g, b)), rgb, 1e-4);
+            let (l, a, b) = color.to_oklab();
+            assert_close(round_trip(Color::from_oklab(l, a, b)), rgb, 1e-3);
+            let (l, c, h) = color.to_oklch();
+            assert_close(round_trip(Color::from_oklch(l, c, h)), rgb, 1e-3);
+            let (l, a, b) = color.to_lab();
+            assert_close(round_trip(Color::from_lab(l, a, b)), rgb, 1e-3);
+        }
+
+        assert_close(Color::new(1.0, 0.0, 0.0).to_hsv(), (0.0, 1.0, 1.0), 1e-4);
+        assert_close(Color::new(1.0, 1.0, 1.0).to_oklab(), (1.0, 0.0, 0.0), 1e-3);
+        assert_close(Color::new(1.0, 1.0, 1.0).to_lab(), (100.0, 0.0, 0.0), 1e-2);
+        assert_close(
+            Color::new(1.0, 0.0, 0.0).to_lab(),
+            (53.24, 80.09, 67.20),
+            1e-1,
+        );
+
+        let color = Color::from_quantized(255, 127, 0);
+        assert_eq!(color.delta_e(&color), 0.0);
+        assert!(color.delta_e(&Color::from_quantized(255, 128, 0)) < 0.01);
+        assert!(color.delta_e(&Color::from_quantized(0, 127, 255)) > 0.2);
+    }
+
+    #[test]
+    fn test_rgba() {
+        let rgba = Rgba::from_quantized(255, 127, 0, 64);
+        assert_eq!(rgba.to_hex(), "#FF7F0040");
+        assert_eq!(Rgba::from_hex(&rgba.to_hex()), Some(rgba));
+        assert_eq!(Rgba::from_hex("#F00"), Some(Rgba::new(1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(Rgba::from_hex("#F008").unwrap().a, 8.0 / 15.0);
+        assert_eq!(
+            Color::from_hex("#FF000080"),
+            Some(Color::new(1.0, 0.0, 0.0))
+        );
+
+        let red = Rgba::new(1.0, 0.0, 0.0, 0.5);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        assert_eq!(red.over_color(&blue), Color::new(0.5, 0.0, 0.5));
+        assert_eq!(red.over(&blue.into()), Rgba::new(0.5, 0.0, 0.5, 1.0));
+        assert_eq!(red.over(&Rgba::default()), red);
+        let over = red.over(&Rgba::new(0.0, 0.0, 1.0, 0.5));
+        assert_eq!(over.a, 0.75);
+        assert!((over.r - 2.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Color::new(0.5, 0.25, 1.0);
+        let b = Color::new(0.25, 0.5, 0.5);
+        assert_eq!(a + b, Color::new(0.75, 0.75, 1.5));
+        assert_eq!(a - b, Color::new(0.25, -0.25, 0.5));
+        assert_eq!(a * 2.0, Color::new(1.0, 0.5, 2.0));
+        assert_eq!(a * b, Color::new(0.125, 0.125, 0.5));
+        assert_eq!((a + b).clamp(), Color::new(0.75, 0.75, 1.0));
+        assert_eq!(a.lerp(&b, 0.5), Color::new(0.375, 0.375, 0.75));
+    }
 }
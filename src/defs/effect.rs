@@ -1,30 +1,60 @@
 //! https://docs.polychromatic.app/config/effects/
 
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::BTreeMap, path::PathBuf};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+/// Keys are the `x` then `y` position of each color, read as strings so that invalid positions
+/// can be reported per frame
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct EffectFrame {
-    pub cols: HashMap<String, HashMap<String, String>>,
+pub struct EffectFrame<K: Ord = u32> {
+    pub cols: BTreeMap<K, BTreeMap<K, String>>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct Effect {
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EffectHeader {
     pub name: String,
     pub r#type: i64,
+    #[serde(default)]
     pub author: String,
+    #[serde(default)]
     pub icon: PathBuf,
+    #[serde(default)]
     pub summary: String,
     pub map_device: String,
+    #[serde(default)]
     pub map_device_icon: String,
+    #[serde(default)]
     pub map_graphic: String,
     pub map_cols: u32,
     pub map_rows: u32,
+    #[serde(default)]
     pub save_format: i64,
+    #[serde(default)]
     pub revision: u64,
     pub fps: u32,
     pub r#loop: bool,
-    pub frames: Vec<EffectFrame>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Effect<K: Ord = u32> {
+    #[serde(flatten)]
+    pub header: EffectHeader,
+    pub frames: Vec<EffectFrame<K>>,
+}
+
+/// Fields shared by every effect type
+#[derive(Debug, Deserialize)]
+pub struct EffectMetadata {
+    pub name: String,
+    pub r#type: i64,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub icon: PathBuf,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub map_device: Option<String>,
 }
@@ -0,0 +1,65 @@
+//! https://docs.polychromatic.app/config/effects/
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WaveDirection {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "properties", rename_all = "lowercase")]
+pub enum LayerKind {
+    /// Single color over every key
+    Static { color: Color },
+    /// Colors spread evenly across the keyboard
+    Gradient {
+        colors: Vec<Color>,
+        direction: WaveDirection,
+    },
+    /// Hardware style spectrum wave
+    Wave { direction: WaveDirection, speed: u8 },
+    /// Cycle through the color spectrum on every key
+    Spectrum { speed: u8 },
+    /// Fade each color in and out in turn
+    Breathing { colors: Vec<Color>, speed: u8 },
+    /// Per key `(x, y, color)` overrides
+    Keys { keys: Vec<(u32, u32, Color)> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: LayerKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LayeredEffect {
+    pub name: String,
+    pub r#type: i64,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub icon: PathBuf,
+    #[serde(default)]
+    pub summary: String,
+    pub map_device: String,
+    #[serde(default)]
+    pub map_device_icon: String,
+    #[serde(default)]
+    pub map_graphic: String,
+    pub map_cols: u32,
+    pub map_rows: u32,
+    #[serde(default)]
+    pub save_format: i64,
+    #[serde(default)]
+    pub revision: u64,
+    pub layers: Vec<Layer>,
+}
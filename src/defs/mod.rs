@@ -1,3 +1,15 @@
 mod effect;
+mod layered_effect;
+mod scripted_effect;
 
 pub use effect::*;
+pub use layered_effect::*;
+pub use scripted_effect::*;
+
+pub fn to_json<T: serde::Serialize>(value: &T, pretty: bool) -> serde_json::Result<String> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
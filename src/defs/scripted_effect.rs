@@ -0,0 +1,62 @@
+//! https://docs.polychromatic.app/config/effects/
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Color;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ParameterKind {
+    Color {
+        default: Color,
+    },
+    Int {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Float {
+        default: f64,
+        min: f64,
+        max: f64,
+        step: f64,
+    },
+    Bool {
+        default: bool,
+    },
+    Choice {
+        default: String,
+        options: Vec<String>,
+    },
+}
+
+/// User editable value passed to the script
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Parameter {
+    /// Variable name the script reads the value from
+    pub var: String,
+    pub label: String,
+    #[serde(flatten)]
+    pub kind: ParameterKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScriptedEffect {
+    pub name: String,
+    pub r#type: i64,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub icon: PathBuf,
+    #[serde(default)]
+    pub summary: String,
+    pub designed_for: Vec<String>,
+    #[serde(default)]
+    pub parameters: Vec<Parameter>,
+    #[serde(default)]
+    pub save_format: i64,
+    #[serde(default)]
+    pub revision: u64,
+}
@@ -18,6 +18,26 @@ impl ToString for Device {
 }
 
 impl Device {
+    /// Find the device polychromatic refers to by `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Keyboard::from_name(name).map(Device::Keyboard)
+    }
+
+    /// Resolve the `map_device` of an effect file, checking it has the expected matrix
+    pub(crate) fn from_map(name: &str, cols: u32, rows: u32) -> Result<Self, PolychromaticError> {
+        let device = Self::from_name(name)
+            .ok_or_else(|| PolychromaticError::UnknownDevice(name.to_owned()))?;
+        let (width, height) = device
+            .matrix()
+            .ok_or(PolychromaticError::DeviceUnsupportedEffects(device))?;
+        if (width, height) != (cols, rows) {
+            return Err(PolychromaticError::MatrixMismatch(
+                cols, rows, device, width, height,
+            ));
+        }
+        Ok(device)
+    }
+
     pub(crate) fn icon(&self) -> &str {
         match self {
             Device::Keyboard(_) => "keyboard",
@@ -29,6 +49,44 @@ impl Device {
             Device::Keyboard(keyboard) => keyboard.matrix(),
         }
     }
+
+    /// Polychromatic graphic file for the device, if it has one
+    pub fn map_graphic(&self, layout: KeyboardLayout) -> Option<String> {
+        match self {
+            Device::Keyboard(keyboard) => keyboard.map_graphic(layout),
+        }
+    }
+}
+
+/// Physical key layout, used to pick the matching polychromatic graphic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+pub enum KeyboardLayout {
+    #[default]
+    EnUS,
+    EnGB,
+    DeDE,
+    JaJP,
+}
+
+impl KeyboardLayout {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::EnUS => "en_US",
+            Self::EnGB => "en_GB",
+            Self::DeDE => "de_DE",
+            Self::JaJP => "ja_JP",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::iter().find(|layout| layout.code() == code)
+    }
+
+    /// Layout of a graphic file name from [`Device::map_graphic`]
+    pub(crate) fn from_map_graphic(map_graphic: &str) -> Option<Self> {
+        let name = map_graphic.strip_suffix(".svg")?;
+        Self::from_code(name.get(name.len().checked_sub(5)?..)?)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
@@ -184,6 +242,11 @@ impl Keyboard {
             .collect())
     }
 
+    /// Find the keyboard polychromatic refers to by `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Keyboard::iter().find(|kb| kb.to_string() == name)
+    }
+
     /// Returns the first detected Razer keyboard
     pub fn detect_one() -> Result<Self, PolychromaticError> {
         Self::detect()?
@@ -311,4 +374,121 @@ impl Keyboard {
             Self::RazerBlackWidowV3Tenkeyless => Some((18, 6)),
         }
     }
+
+    /// Polychromatic graphic base name and the layouts it is available in, an empty layout list
+    /// means the graphic isn't localized
+    fn graphic(&self) -> Option<(&'static str, &'static [KeyboardLayout])> {
+        const US: &[KeyboardLayout] = &[KeyboardLayout::EnUS];
+        const EU: &[KeyboardLayout] = &[
+            KeyboardLayout::EnUS,
+            KeyboardLayout::EnGB,
+            KeyboardLayout::DeDE,
+        ];
+        const JP: &[KeyboardLayout] = &[KeyboardLayout::JaJP];
+        match self {
+            Self::RazerBlackWidowUltimate2012 => None,
+            Self::RazerBlackWidowStealthEdition => None,
+            Self::RazerAnansi => None,
+            Self::RazerDeathstalkerEssential => None,
+            Self::RazerBlackWidowUltimate2013 => None,
+            Self::RazerBlackWidowStealth => None,
+            Self::RazerBlackWidowTournamentEdition2014 => None,
+            Self::RazerDeathstalkerExpert => None,
+            Self::RazerBlackWidowChroma => Some(("blackwidow_chroma", EU)),
+            Self::RazerDeathStalkerChroma => Some(("deathstalker_chroma", &[])),
+            Self::RazerBlackWidowChromaTournamentEdition => Some(("blackwidow_chroma_te", EU)),
+            Self::RazerBlackWidowChromaOverwatch => Some(("blackwidow_chroma", EU)),
+            Self::RazerBlackWidowUltimate2016 => Some(("blackwidow_chroma", EU)),
+            Self::RazerBlackWidowXChroma => Some(("blackwidow_x_chroma", EU)),
+            Self::RazerBlackWidowXUltimate => Some(("blackwidow_x_chroma", EU)),
+            Self::RazerBlackWidowXChromaTournamentEdition => Some(("blackwidow_x_chroma_te", EU)),
+            Self::RazerOrnataChroma => Some(("ornata_chroma", EU)),
+            Self::RazerOrnata => Some(("ornata_chroma", EU)),
+            Self::RazerBlackWidowChromaV2 => Some(("blackwidow_chroma_v2", EU)),
+            Self::RazerHuntsmanElite => Some(("huntsman_elite", EU)),
+            Self::RazerHuntsman => Some(("huntsman", EU)),
+            Self::RazerBlackWidowElite => Some(("blackwidow_elite", EU)),
+            Self::RazerCynosaChroma => Some(("cynosa_chroma", EU)),
+            Self::RazerCynosaChromaPro => Some(("cynosa_chroma", EU)),
+            Self::RazerBlackWidowLite => None,
+            Self::RazerBlackWidowEssential => Some(("blackwidow_chroma", EU)),
+            Self::RazerCynosaLite => None,
+            Self::RazerBlackWidow2019 => Some(("blackwidow_chroma", EU)),
+            Self::RazerHuntsmanTournamentEdition => Some(("huntsman_tournament", EU)),
+            Self::RazerBlackWidowV3 => Some(("blackwidow_v3", EU)),
+            Self::RazerHuntsmanMini => Some(("huntsman_mini", EU)),
+            Self::RazerBlackWidowV3MiniHyperspeedWired => Some(("blackwidow_v3_mini", US)),
+            Self::RazerBlackWidowV3ProWired => Some(("blackwidow_v3", EU)),
+            Self::RazerBlackWidowV3ProWireless => Some(("blackwidow_v3", EU)),
+            Self::RazerOrnataV2 => Some(("ornata_chroma", EU)),
+            Self::RazerCynosaV2 => Some(("cynosa_chroma", EU)),
+            Self::RazerHuntsmanV2Analog => Some(("huntsman_v2_analog", US)),
+            Self::RazerHuntsmanMiniJP => Some(("huntsman_mini", JP)),
+            Self::RazerBook13_2020 => None,
+            Self::RazerHuntsmanV2Tenkeyless => Some(("huntsman_v2_tkl", US)),
+            Self::RazerHuntsmanV2 => Some(("huntsman_v2", US)),
+            Self::RazerBlackWidowV3MiniHyperspeedWireless => Some(("blackwidow_v3_mini", US)),
+            Self::RazerHuntsmanMiniAnalog => Some(("huntsman_mini", EU)),
+            Self::RazerBlackWidowV4 => Some(("blackwidow_v4", US)),
+            Self::RazerBlackWidowV4Pro => Some(("blackwidow_v4", US)),
+            Self::RazerDeathStalkerV2ProWireless => Some(("deathstalker_v2", US)),
+            Self::RazerDeathStalkerV2ProWired => Some(("deathstalker_v2", US)),
+            Self::RazerBlackWidowV4X => Some(("blackwidow_v4_x", US)),
+            Self::RazerDeathStalkerV2 => Some(("deathstalker_v2", US)),
+            Self::RazerDeathStalkerV2ProTKLWireless => Some(("deathstalker_v2_tkl", US)),
+            Self::RazerDeathStalkerV2ProTKLWired => Some(("deathstalker_v2_tkl", US)),
+            Self::RazerOrnataV3 => None,
+            Self::RazerOrnataV3X => None,
+            Self::RazerOrnataV3Tenkeyless => None,
+            Self::RazerBlackWidowV3Tenkeyless => Some(("blackwidow_v3_tkl", EU)),
+        }
+    }
+
+    /// Polychromatic graphic file for `layout`, falling back to en_US or the first available
+    /// layout when the graphic doesn't exist for `layout`
+    pub fn map_graphic(&self, layout: KeyboardLayout) -> Option<String> {
+        let (name, layouts) = self.graphic()?;
+        let Some(layout) = [layout, KeyboardLayout::EnUS]
+            .into_iter()
+            .find(|layout| layouts.contains(layout))
+            .or(layouts.first().copied())
+        else {
+            return Some(format!("{name}.svg"));
+        };
+        Some(format!("{name}_{}.svg", layout.code()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Device, Keyboard, KeyboardLayout};
+
+    #[test]
+    fn test_map_graphic() {
+        let device = Device::Keyboard(Keyboard::RazerBlackWidowV3);
+        assert_eq!(
+            device.map_graphic(KeyboardLayout::EnGB).as_deref(),
+            Some("blackwidow_v3_en_GB.svg")
+        );
+        assert_eq!(
+            device.map_graphic(KeyboardLayout::JaJP).as_deref(),
+            Some("blackwidow_v3_en_US.svg")
+        );
+        assert_eq!(
+            Keyboard::RazerHuntsmanMiniJP
+                .map_graphic(KeyboardLayout::EnUS)
+                .as_deref(),
+            Some("huntsman_mini_ja_JP.svg")
+        );
+        assert_eq!(
+            Keyboard::RazerDeathStalkerChroma
+                .map_graphic(KeyboardLayout::EnUS)
+                .as_deref(),
+            Some("deathstalker_chroma.svg")
+        );
+        assert_eq!(
+            Keyboard::RazerOrnataV3.map_graphic(KeyboardLayout::EnUS),
+            None
+        );
+    }
 }
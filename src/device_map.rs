@@ -0,0 +1,345 @@
+//! Polychromatic SVG device graphics, where each key has the id `x{col}-y{row}`
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use crate::{Color, Effect, EffectMatrix, PolychromaticError, svg};
+
+/// Position of a key in SVG user units, see [`DeviceMap::millimetres_per_unit`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyGeometry {
+    /// Top left corner
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Text drawn on the key
+    pub label: Option<String>,
+}
+
+impl KeyGeometry {
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    /// Geometry with every position and size multiplied by `factor`
+    pub fn scaled(&self, factor: f32) -> KeyGeometry {
+        KeyGeometry {
+            x: self.x * factor,
+            y: self.y * factor,
+            width: self.width * factor,
+            height: self.height * factor,
+            label: self.label.clone(),
+        }
+    }
+}
+
+/// SVG graphic of a device with key shapes tied to matrix cells
+#[derive(Debug, Clone)]
+pub struct DeviceMap {
+    svg: String,
+    /// Keys without any shapes have no geometry
+    keys: BTreeMap<(u32, u32), Option<KeyGeometry>>,
+    millimetres_per_unit: f32,
+    /// Byte offset of the closing tag of the root element, styles are inserted there
+    insert_at: usize,
+}
+
+/// Matrix cell of a key id like `x3-y1`
+fn parse_key_id(id: &str) -> Option<(u32, u32)> {
+    let (x, y) = id.strip_prefix('x')?.split_once("-y")?;
+    Some((x.parse().ok()?, y.parse().ok()?))
+}
+
+/// Text of the labels inside a key, lines joined by spaces
+fn label(node: roxmltree::Node) -> Option<String> {
+    let label = node
+        .descendants()
+        .filter(|node| node.has_tag_name("text"))
+        .flat_map(|text| text.descendants().filter(|node| node.is_text()))
+        .filter_map(|node| node.text())
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!label.is_empty()).then_some(label)
+}
+
+/// Millimetres per user unit, user units are CSS pixels without a `viewBox`
+fn millimetres_per_unit(root: roxmltree::Node) -> f32 {
+    const MILLIMETRES_PER_PIXEL: f32 = 25.4 / 96.0;
+    let width = root.attribute("width").and_then(|width| {
+        let number = svg::length(width)?;
+        let unit = width.trim().trim_start_matches(|char: char| {
+            char.is_ascii_digit() || matches!(char, '.' | '-' | '+' | 'e' | 'E')
+        });
+        let millimetres = match unit {
+            "mm" => 1.0,
+            "cm" => 10.0,
+            "in" => 25.4,
+            "pt" => 25.4 / 72.0,
+            "pc" => 25.4 / 6.0,
+            "" | "px" => MILLIMETRES_PER_PIXEL,
+            _ => return None,
+        };
+        Some(number * millimetres)
+    });
+    let view_box_width = root
+        .attribute("viewBox")
+        .and_then(|view_box| view_box.split([' ', ',']).filter(|n| !n.is_empty()).nth(2))
+        .and_then(svg::length);
+    match (width, view_box_width) {
+        (Some(width), Some(view_box_width)) if view_box_width > 0.0 => width / view_box_width,
+        _ => MILLIMETRES_PER_PIXEL,
+    }
+}
+
+fn key_id((x, y): (u32, u32)) -> String {
+    format!("x{x}-y{y}")
+}
+
+/// Selects the key element and every shape inside it, leaving labels alone
+fn key_selector(key: (u32, u32)) -> String {
+    let id = key_id(key);
+    format!("#{id},#{id} :not(text):not(tspan)")
+}
+
+impl DeviceMap {
+    pub fn parse<S: Into<String>>(svg: S) -> Result<Self, PolychromaticError> {
+        let svg = svg.into();
+        let document = roxmltree::Document::parse(&svg)?;
+        let root = document.root_element();
+        if !root.has_tag_name("svg") {
+            return Err(PolychromaticError::CannotParseSvg(
+                "Root element isn't <svg>",
+            ));
+        }
+        let insert_at = svg[..root.range().end]
+            .rfind("</")
+            .filter(|_| !svg[..root.range().end].ends_with("/>"))
+            .ok_or(PolychromaticError::CannotParseSvg("Graphic has no keys"))?;
+        let keys = document
+            .descendants()
+            .filter_map(|node| Some((parse_key_id(node.attribute("id")?)?, node)))
+            .map(|(key, node)| {
+                let geometry = svg::bounds(node).map(|bounds| KeyGeometry {
+                    x: bounds.min.0,
+                    y: bounds.min.1,
+                    width: bounds.max.0 - bounds.min.0,
+                    height: bounds.max.1 - bounds.min.1,
+                    label: label(node),
+                });
+                (key, geometry)
+            })
+            .collect();
+        Ok(Self {
+            millimetres_per_unit: millimetres_per_unit(root),
+            svg,
+            keys,
+            insert_at,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PolychromaticError> {
+        Self::parse(std::fs::read_to_string(path)?)
+    }
+
+    /// Matrix cells that have a key in the graphic
+    pub fn keys(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.keys.keys().copied()
+    }
+
+    /// Geometry of the key at a matrix cell
+    pub fn key(&self, x: u32, y: u32) -> Option<&KeyGeometry> {
+        self.keys.get(&(x, y))?.as_ref()
+    }
+
+    /// Geometry of every key that has a shape, in matrix order
+    pub fn geometry(&self) -> impl Iterator<Item = ((u32, u32), &KeyGeometry)> + '_ {
+        self.keys
+            .iter()
+            .filter_map(|(key, geometry)| Some((*key, geometry.as_ref()?)))
+    }
+
+    /// Size of an SVG user unit from the graphic's `width` and `viewBox`, use with
+    /// [`KeyGeometry::scaled`]
+    pub fn millimetres_per_unit(&self) -> f32 {
+        self.millimetres_per_unit
+    }
+
+    fn with_style(&self, style: &str) -> String {
+        format!(
+            "{}<style>{style}</style>\n{}",
+            &self.svg[..self.insert_at],
+            &self.svg[self.insert_at..]
+        )
+    }
+
+    /// Graphic with each key filled from the matching cell of `frame`
+    pub fn render_frame(&self, frame: &EffectMatrix) -> String {
+        let mut style = String::new();
+        for key in self.keys() {
+            if let Some(color) = frame.get(key.0, key.1) {
+                // Important beats fills set in style attributes.
+                let selector = key_selector(key);
+                write!(style, "{selector}{{fill:{}!important}}", color.to_hex()).unwrap();
+            }
+        }
+        self.with_style(&style)
+    }
+
+    /// A graphic for each frame of `effect`, see [`DeviceMap::render_frame`]
+    pub fn render_frames(&self, effect: &Effect) -> Vec<String> {
+        effect
+            .frames()
+            .iter()
+            .map(|frame| self.render_frame(frame))
+            .collect()
+    }
+
+    /// Graphic animating every key with CSS at the effect's FPS, looping if the effect does
+    pub fn render_animated(&self, effect: &Effect) -> Result<String, PolychromaticError> {
+        let frames = effect.frames();
+        if frames.is_empty() {
+            return Err(PolychromaticError::NoFrames);
+        }
+        let duration = frames.len() as f32 / effect.fps() as f32;
+        let iterations = if effect.r#loop {
+            "infinite"
+        } else {
+            "1 forwards"
+        };
+
+        let mut style = String::new();
+        for key in self.keys() {
+            let colors = frames
+                .iter()
+                .map(|frame| frame.get(key.0, key.1).copied())
+                .collect::<Option<Vec<Color>>>();
+            let Some(colors) = colors else {
+                continue;
+            };
+            let name = format!("k{}-{}", key.0, key.1);
+            write!(style, "@keyframes {name}{{").unwrap();
+            let mut previous = None;
+            for (index, color) in colors.iter().enumerate() {
+                let hex = color.to_hex();
+                if previous.as_ref() != Some(&hex) {
+                    let percent = index as f32 / frames.len() as f32 * 100.0;
+                    write!(style, "{percent:.4}%{{fill:{hex}}}").unwrap();
+                    previous = Some(hex);
+                }
+            }
+            // The last frame is held after the animation ends instead of the original fill.
+            write!(style, "100%{{fill:{}}}}}", previous.unwrap()).unwrap();
+            write!(
+                style,
+                "{}{{animation:{name} {duration}s step-end {iterations}}}",
+                key_selector(key)
+            )
+            .unwrap();
+        }
+        Ok(self.with_style(&style))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{Color, Device, DeviceMap, Effect, EffectMatrix, KeyGeometry, Keyboard};
+
+    const SVG: &str = include_str!("../tests/fixtures/keyboard.svg");
+
+    #[test]
+    fn test_render_frame() {
+        let map = DeviceMap::parse(SVG).unwrap();
+        assert_eq!(
+            map.keys().collect::<Vec<_>>(),
+            [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]
+        );
+
+        let mut frame = EffectMatrix::new(2, 2);
+        frame.set(Color::new(1.0, 0.0, 0.0), 1, 1);
+        let svg = map.render_frame(&frame);
+        assert!(roxmltree::Document::parse(&svg).is_ok());
+        assert!(svg.ends_with(
+            "#x1-y1,#x1-y1 :not(text):not(tspan){fill:#FF0000!important}</style>\n</svg>\n"
+        ));
+        // Keys outside the frame are left alone.
+        assert!(!svg.contains("#x2-y0"));
+
+        let map =
+            DeviceMap::parse(r#"<svg><rect id="x0-y0" width="é1" height="2"/></svg>"#).unwrap();
+        assert_eq!(map.key(0, 0).unwrap().width, 1.0);
+
+        assert!(DeviceMap::parse("<svg/>").is_err());
+        assert!(DeviceMap::parse("<svg>").is_err());
+    }
+
+    #[test]
+    fn test_geometry() {
+        let map = DeviceMap::parse(SVG).unwrap();
+        assert_eq!(map.millimetres_per_unit(), 1.0);
+        assert_eq!(
+            map.key(0, 0),
+            Some(&KeyGeometry {
+                x: 2.0,
+                y: 2.0,
+                width: 18.0,
+                height: 18.0,
+                label: Some("Esc".to_owned()),
+            })
+        );
+        let centers = map
+            .geometry()
+            .map(|(key, geometry)| (key, geometry.center()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            centers,
+            [
+                ((0, 0), (11.0, 11.0)),
+                ((0, 1), (11.0, 30.0)),
+                ((1, 0), (31.0, 11.0)),
+                ((1, 1), (41.0, 30.0)),
+                ((2, 0), (51.0, 11.0)),
+            ]
+        );
+        assert_eq!(map.key(1, 1).unwrap().label.as_deref(), Some("Space"));
+        assert_eq!(map.key(2, 0).unwrap().label, None);
+
+        let scaled = DeviceMap::parse(
+            r#"<svg width="96" viewBox="0 0 48 48"><rect id="x0-y0" width="24" height="24"/></svg>"#,
+        )
+        .unwrap();
+        let key = scaled
+            .key(0, 0)
+            .unwrap()
+            .scaled(scaled.millimetres_per_unit());
+        assert!((key.width - 12.7).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_render_animated() {
+        let map = DeviceMap::parse(SVG).unwrap();
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.set_fps(4).unwrap();
+        effect.render_pixels(Duration::from_secs(1), |x, _, time| {
+            if time.index >= 2 && x == 0 {
+                Color::new(1.0, 1.0, 1.0)
+            } else {
+                Color::default()
+            }
+        });
+
+        let svg = map.render_animated(&effect).unwrap();
+        assert!(roxmltree::Document::parse(&svg).is_ok());
+        assert!(svg.contains(
+            "@keyframes k0-0{0.0000%{fill:#000000}50.0000%{fill:#FFFFFF}100%{fill:#FFFFFF}}\
+             #x0-y0,#x0-y0 :not(text):not(tspan){animation:k0-0 1s step-end infinite}"
+        ));
+        assert_eq!(map.render_frames(&effect).len(), 4);
+    }
+}
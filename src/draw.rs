@@ -0,0 +1,421 @@
+//! Drawing on an [`EffectMatrix`], everything is clipped to the matrix and positions may lie
+//! outside of it
+
+use std::ops::RangeInclusive;
+
+use crate::EffectMatrix;
+
+impl<T: Clone> EffectMatrix<T> {
+    /// Set a key from signed coordinates, ignoring keys outside of the matrix
+    pub fn plot(&mut self, x: i32, y: i32, value: T) {
+        if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+            self.set(value, x, y);
+        }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.values_mut().fill(value);
+    }
+
+    /// Line from `x0` to `x1` inclusive
+    pub fn hline(&mut self, x0: i32, x1: i32, y: i32, value: T) {
+        let (x0, x1) = (x0.min(x1).max(0), x0.max(x1).min(self.width() as i32 - 1));
+        for x in x0..=x1 {
+            self.plot(x, y, value.clone());
+        }
+    }
+
+    /// Line from `y0` to `y1` inclusive
+    pub fn vline(&mut self, x: i32, y0: i32, y1: i32, value: T) {
+        let (y0, y1) = (y0.min(y1).max(0), y0.max(y1).min(self.height() as i32 - 1));
+        for y in y0..=y1 {
+            self.plot(x, y, value.clone());
+        }
+    }
+
+    /// Bresenham line between two points inclusive
+    pub fn line(&mut self, from: (i32, i32), to: (i32, i32), value: T) {
+        // Only the part inside the matrix is walked, far away end points would take forever.
+        let Some(((x0, y0), (x1, y1))) = clip_line(from, to, self.width(), self.height()) else {
+            return;
+        };
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+        loop {
+            self.plot(x as i32, y as i32, value.clone());
+            if x == x1 && y == y1 {
+                break;
+            }
+            let error2 = 2 * error;
+            if error2 >= dy {
+                error += dy;
+                x += sx;
+            }
+            if error2 <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Outline of the rectangle with its top left corner at `x`, `y`
+    pub fn rect(&mut self, x: i32, y: i32, width: u32, height: u32, value: T) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (x1, y1) = (last(x, width), last(y, height));
+        self.hline(x, x1, y, value.clone());
+        self.hline(x, x1, y1, value.clone());
+        self.vline(x, y, y1, value.clone());
+        self.vline(x1, y, y1, value);
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, value: T) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (y0, y1) = (y.max(0), last(y, height).min(self.height() as i32 - 1));
+        for y in y0..=y1 {
+            self.hline(x, last(x, width), y, value.clone());
+        }
+    }
+
+    /// Outline of a circle centered on a key
+    pub fn circle(&mut self, cx: i32, cy: i32, radius: u32, value: T) {
+        self.ellipse(cx, cy, radius, radius, value);
+    }
+
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: u32, value: T) {
+        self.fill_ellipse(cx, cy, radius, radius, value);
+    }
+
+    /// Outline of an axis aligned ellipse centered on a key
+    pub fn ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, value: T) {
+        let (cx, cy) = (cx as i64, cy as i64);
+        for (dx, dy) in self.visible_ellipse_points(cx, cy, rx, ry) {
+            for (px, py) in [(dx, dy), (-dx, dy), (dx, -dy), (-dx, -dy)] {
+                self.plot(saturate(cx + px), saturate(cy + py), value.clone());
+            }
+        }
+    }
+
+    pub fn fill_ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, value: T) {
+        let (cx, cy) = (cx as i64, cy as i64);
+        for (dx, dy) in self.visible_ellipse_points(cx, cy, rx, ry) {
+            let (x0, x1) = (saturate(cx - dx), saturate(cx + dx));
+            self.hline(x0, x1, saturate(cy + dy), value.clone());
+            self.hline(x0, x1, saturate(cy - dy), value.clone());
+        }
+    }
+
+    /// [`ellipse_points`] limited to the rows and columns that can land inside the matrix
+    fn visible_ellipse_points(&self, cx: i64, cy: i64, rx: u32, ry: u32) -> Vec<(i64, i64)> {
+        if self.width() == 0 || self.height() == 0 {
+            return Vec::new();
+        }
+        let columns = visible_offsets(cx, self.width());
+        let rows = visible_offsets(cy, self.height());
+        ellipse_points(rx, ry, columns, rows)
+    }
+
+    /// Outline connecting `points`, closing back to the first point
+    pub fn polygon(&mut self, points: &[(i32, i32)], value: T) {
+        for (i, point) in points.iter().enumerate() {
+            self.line(*point, points[(i + 1) % points.len()], value.clone());
+        }
+    }
+
+    /// Fill keys whose centers are inside `points` by the even-odd rule
+    pub fn fill_polygon(&mut self, points: &[(i32, i32)], value: T) {
+        if points.len() < 3 {
+            return self.polygon(points, value);
+        }
+        for y in 0..self.height() as i32 {
+            let center_y = y as f32 + 0.5;
+            let mut crossings = points
+                .iter()
+                .zip(points.iter().cycle().skip(1))
+                .filter_map(|(&(x0, y0), &(x1, y1))| {
+                    let (x0, y0, x1, y1) = (
+                        x0 as f32 + 0.5,
+                        y0 as f32 + 0.5,
+                        x1 as f32 + 0.5,
+                        y1 as f32 + 0.5,
+                    );
+                    if (y0 <= center_y) == (y1 <= center_y) {
+                        return None;
+                    }
+                    Some(x0 + (center_y - y0) / (y1 - y0) * (x1 - x0))
+                })
+                .collect::<Vec<_>>();
+            crossings.sort_by(f32::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let x0 = (span[0] - 0.5).ceil() as i32;
+                let x1 = (span[1] - 0.5).floor() as i32;
+                if x0 <= x1 {
+                    self.hline(x0, x1, y, value.clone());
+                }
+            }
+        }
+        // Keep the edges solid where they pass between key centers.
+        self.polygon(points, value);
+    }
+
+    /// Copy the `width` by `height` region of `source` at `source_x`, `source_y` to `x`, `y`
+    #[allow(clippy::too_many_arguments)]
+    pub fn blit(
+        &mut self,
+        source: &EffectMatrix<T>,
+        source_x: i32,
+        source_y: i32,
+        width: u32,
+        height: u32,
+        x: i32,
+        y: i32,
+    ) {
+        // Only offsets that are inside both the source and this matrix are walked.
+        let offsets = |offset: i32, source_offset: i32, size: u32, length: u32, source_length| {
+            let (offset, source_offset) = (offset as i64, source_offset as i64);
+            let start = 0.max(-offset).max(-source_offset);
+            let end = (size as i64)
+                .min(length as i64 - offset)
+                .min(source_length as i64 - source_offset);
+            start..end
+        };
+        let rows = offsets(y, source_y, height, self.height(), source.height());
+        let columns = offsets(x, source_x, width, self.width(), source.width());
+        for dy in rows {
+            for dx in columns.clone() {
+                let (sx, sy) = (source_x as i64 + dx, source_y as i64 + dy);
+                if let Some(value) = source.get(sx as u32, sy as u32) {
+                    self.set(
+                        value.clone(),
+                        (x as i64 + dx) as u32,
+                        (y as i64 + dy) as u32,
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<T: Clone + Default> EffectMatrix<T> {
+    /// Reset every key to the default value, black for colors
+    pub fn clear(&mut self) {
+        self.fill(T::default());
+    }
+}
+
+impl<T: Clone + PartialEq> EffectMatrix<T> {
+    /// Replace the 4-connected area of keys matching the key at `x`, `y`
+    pub fn flood_fill(&mut self, x: u32, y: u32, value: T) {
+        let Some(target) = self.get(x, y).cloned() else {
+            return;
+        };
+        if target == value {
+            return;
+        }
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            match self.get_mut(x, y) {
+                Some(key) if *key == target => *key = value.clone(),
+                _ => continue,
+            }
+            stack.push((x + 1, y));
+            stack.push((x, y + 1));
+            if let Some(x) = x.checked_sub(1) {
+                stack.push((x, y));
+            }
+            if let Some(y) = y.checked_sub(1) {
+                stack.push((x, y));
+            }
+        }
+    }
+}
+
+/// Clamp to the range of `i32`, positions outside of it are never inside the matrix
+fn saturate(value: i64) -> i32 {
+    value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// Last position of a span of `length` keys starting at `start`, saturating at the edge of `i32`
+fn last(start: i32, length: u32) -> i32 {
+    saturate(start as i64 + length as i64 - 1)
+}
+
+/// Offsets from `center` in either direction that land on one of `length` keys
+fn visible_offsets(center: i64, length: u32) -> RangeInclusive<i64> {
+    let last = length as i64 - 1;
+    0.max(-center).max(center - last)..=(last - center).max(center)
+}
+
+/// Liang-Barsky clipping of a line to the key centers of a `width` by `height` matrix, `None`
+/// when the line misses the matrix
+fn clip_line(
+    (x0, y0): (i32, i32),
+    (x1, y1): (i32, i32),
+    width: u32,
+    height: u32,
+) -> Option<((i64, i64), (i64, i64))> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let (x0, y0, x1, y1) = (x0 as f64, y0 as f64, x1 as f64, y1 as f64);
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in [
+        (-dx, x0),
+        (dx, (width - 1) as f64 - x0),
+        (-dy, y0),
+        (dy, (height - 1) as f64 - y0),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    let point = |t: f64| ((x0 + t * dx).round() as i64, (y0 + t * dy).round() as i64);
+    Some((point(t0), point(t1)))
+}
+
+/// Offsets of the edge of one quadrant of an ellipse, walked by row then by column so both the
+/// steep and shallow parts are solid. Only rows in `rows` and columns in `columns` are walked.
+fn ellipse_points(
+    rx: u32,
+    ry: u32,
+    columns: RangeInclusive<i64>,
+    rows: RangeInclusive<i64>,
+) -> Vec<(i64, i64)> {
+    let (rx, ry) = (rx as f64 + 0.5, ry as f64 + 0.5);
+    let mut points = Vec::new();
+    for dy in *rows.start()..=(*rows.end()).min(ry as i64) {
+        let t = 1.0 - (dy as f64 / ry).powi(2);
+        if t >= 0.0 {
+            points.push(((rx * t.sqrt()).floor() as i64, dy));
+        }
+    }
+    for dx in *columns.start()..=(*columns.end()).min(rx as i64) {
+        let t = 1.0 - (dx as f64 / rx).powi(2);
+        if t >= 0.0 {
+            points.push((dx, (ry * t.sqrt()).floor() as i64));
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod test {
+    use crate::EffectMatrix;
+
+    fn render(matrix: &EffectMatrix<u8>) -> String {
+        (0..matrix.height())
+            .map(|y| {
+                (0..matrix.width())
+                    .map(|x| {
+                        if matrix.get(x, y) == Some(&1) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut matrix = EffectMatrix::<u8>::new(6, 4);
+        matrix.line((-2, -1), (7, 3), 1);
+        assert_eq!(render(&matrix), "##....\n..##..\n....##\n......");
+
+        matrix.clear();
+        matrix.hline(-5, 2, 0, 1);
+        matrix.vline(5, 1, 10, 1);
+        assert_eq!(render(&matrix), "###...\n.....#\n.....#\n.....#");
+
+        matrix.clear();
+        matrix.line((i32::MIN + 1, 0), (i32::MAX, 0), 1);
+        matrix.line((2, i32::MIN), (2, i32::MAX), 1);
+        matrix.line((-10, -10), (-1, 20), 1);
+        assert_eq!(render(&matrix), "######\n..#...\n..#...\n..#...");
+    }
+
+    #[test]
+    fn test_shapes() {
+        let mut matrix = EffectMatrix::<u8>::new(6, 4);
+        matrix.rect(1, 0, 4, 4, 1);
+        assert_eq!(render(&matrix), ".####.\n.#..#.\n.#..#.\n.####.");
+
+        matrix.flood_fill(2, 1, 1);
+        assert_eq!(render(&matrix), ".####.\n.####.\n.####.\n.####.");
+        matrix.flood_fill(0, 0, 1);
+        assert_eq!(render(&matrix), "#####.\n#####.\n#####.\n#####.");
+
+        let mut matrix = EffectMatrix::<u8>::new(7, 7);
+        matrix.circle(3, 3, 2, 1);
+        assert_eq!(
+            render(&matrix),
+            ".......\n..###..\n.#...#.\n.#...#.\n.#...#.\n..###..\n......."
+        );
+        matrix.fill_circle(3, 3, 2, 1);
+        assert_eq!(
+            render(&matrix),
+            ".......\n..###..\n.#####.\n.#####.\n.#####.\n..###..\n......."
+        );
+
+        matrix.clear();
+        matrix.circle(i32::MAX, 0, 1, 1);
+        matrix.fill_circle(i32::MIN, 0, 1, 1);
+        matrix.circle(0, 0, u32::MAX, 1);
+        matrix.circle(i32::MAX, i32::MIN, u32::MAX, 1);
+        assert_eq!(render(&matrix), ".......\n".repeat(6) + ".......");
+        matrix.circle(-3, 3, 5, 1);
+        matrix.fill_ellipse(10, 0, 7, 0, 1);
+        assert_eq!(
+            render(&matrix),
+            ".#.####\n..#....\n..#....\n..#....\n..#....\n..#....\n.#....."
+        );
+
+        let mut matrix = EffectMatrix::<u8>::new(5, 5);
+        matrix.fill_polygon(&[(0, 0), (4, 0), (0, 4)], 1);
+        assert_eq!(render(&matrix), "#####\n####.\n###..\n##...\n#....");
+
+        matrix.clear();
+        matrix.fill_rect(3, i32::MAX - 1, u32::MAX, u32::MAX, 1);
+        matrix.fill_rect(3, 1, u32::MAX, 2, 1);
+        matrix.rect(i32::MAX - 1, 0, u32::MAX, 1, 1);
+        matrix.rect(-1, 3, u32::MAX, u32::MAX, 1);
+        assert_eq!(render(&matrix), ".....\n...##\n...##\n#####\n.....");
+    }
+
+    #[test]
+    fn test_blit() {
+        let mut source = EffectMatrix::<u8>::new(3, 2);
+        source.fill(1);
+        let mut matrix = EffectMatrix::<u8>::new(4, 3);
+        matrix.blit(&source, 1, 0, 5, 5, 2, 1);
+        assert_eq!(render(&matrix), "....\n..##\n..##");
+
+        matrix.clear();
+        matrix.blit(&source, 0, 0, u32::MAX, u32::MAX, i32::MAX, 0);
+        matrix.blit(&source, i32::MAX, 0, u32::MAX, u32::MAX, 0, 0);
+        matrix.blit(&source, i32::MIN, i32::MIN, u32::MAX, u32::MAX, 0, 0);
+        assert_eq!(render(&matrix), "....\n....\n....");
+        matrix.blit(&source, -1, 0, u32::MAX, u32::MAX, i32::MIN, 0);
+        matrix.blit(&source, 2, 1, u32::MAX, u32::MAX, 0, 0);
+        assert_eq!(render(&matrix), "#...\n....\n....");
+    }
+}
@@ -0,0 +1,273 @@
+//! Easing functions mapping `0.0..=1.0` progress to interpolation amount
+//!
+//! https://easings.net/
+
+use std::f32::consts::PI;
+
+use crate::Color;
+
+/// Values that can be blended between
+pub trait Interpolate: Clone {
+    /// Blend from `self` at `t = 0.0` to `to` at `t = 1.0`
+    fn interpolate(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        (1.0 - t) * self + t * to
+    }
+}
+
+impl Interpolate for (f32, f32) {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        (self.0.interpolate(&to.0, t), self.1.interpolate(&to.1, t))
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, to: &Self, t: f32) -> Self {
+        self.lerp(to, t)
+    }
+}
+
+/// Which end of the animation an easing is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EasingMode {
+    In,
+    Out,
+    InOut,
+}
+
+impl EasingMode {
+    /// Build this mode out of an ease in function
+    fn apply(&self, ease_in: impl Fn(f32) -> f32, t: f32) -> f32 {
+        match self {
+            Self::In => ease_in(t),
+            Self::Out => 1.0 - ease_in(1.0 - t),
+            Self::InOut if t < 0.5 => ease_in(2.0 * t) / 2.0,
+            Self::InOut => 1.0 - ease_in(2.0 - 2.0 * t) / 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Keep the start value until the end
+    Hold,
+    Quad(EasingMode),
+    Cubic(EasingMode),
+    Quart(EasingMode),
+    Quint(EasingMode),
+    Sine(EasingMode),
+    Expo(EasingMode),
+    Circ(EasingMode),
+    /// Overshoots then settles
+    Back(EasingMode),
+    /// Oscillates around the target
+    Elastic(EasingMode),
+    Bounce(EasingMode),
+    /// CSS style `cubic-bezier(x1, y1, x2, y2)` curve
+    CubicBezier(f32, f32, f32, f32),
+    /// Jump between `n` evenly spaced values, like CSS `steps(n)`
+    Steps(u32),
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    /// Ease `t`, which is clamped to `0.0..=1.0`
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::Hold if t < 1.0 => 0.0,
+            Self::Hold => 1.0,
+            Self::Quad(mode) => mode.apply(|t| t.powi(2), t),
+            Self::Cubic(mode) => mode.apply(|t| t.powi(3), t),
+            Self::Quart(mode) => mode.apply(|t| t.powi(4), t),
+            Self::Quint(mode) => mode.apply(|t| t.powi(5), t),
+            Self::Sine(mode) => mode.apply(|t| 1.0 - (t * PI / 2.0).cos(), t),
+            Self::Expo(mode) => mode.apply(
+                |t| {
+                    if t <= 0.0 {
+                        0.0
+                    } else {
+                        2f32.powf(10.0 * t - 10.0)
+                    }
+                },
+                t,
+            ),
+            Self::Circ(mode) => mode.apply(|t| 1.0 - (1.0 - t * t).sqrt(), t),
+            Self::Back(mode) => mode.apply(
+                |t| {
+                    const C1: f32 = 1.70158;
+                    const C3: f32 = C1 + 1.0;
+                    C3 * t.powi(3) - C1 * t.powi(2)
+                },
+                t,
+            ),
+            Self::Elastic(mode) => mode.apply(
+                |t| {
+                    if t <= 0.0 || t >= 1.0 {
+                        t
+                    } else {
+                        -(2f32.powf(10.0 * t - 10.0))
+                            * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+                    }
+                },
+                t,
+            ),
+            Self::Bounce(mode) => mode.apply(|t| 1.0 - bounce_out(1.0 - t), t),
+            Self::CubicBezier(x1, y1, x2, y2) => cubic_bezier(*x1, *y1, *x2, *y2, t),
+            Self::Steps(0) => t,
+            Self::Steps(steps) => (t * *steps as f32).floor() / *steps as f32,
+            Self::Custom(ease) => ease(t),
+        }
+    }
+
+    /// Blend from `from` to `to` with the eased `t`
+    pub fn interpolate<T: Interpolate>(&self, from: &T, to: &T, t: f32) -> T {
+        from.interpolate(to, self.apply(t))
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N1: f32 = 7.5625;
+    const D1: f32 = 2.75;
+    if t < 1.0 / D1 {
+        N1 * t * t
+    } else if t < 2.0 / D1 {
+        let t = t - 1.5 / D1;
+        N1 * t * t + 0.75
+    } else if t < 2.5 / D1 {
+        let t = t - 2.25 / D1;
+        N1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D1;
+        N1 * t * t + 0.984375
+    }
+}
+
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    fn bezier(p1: f32, p2: f32, s: f32) -> f32 {
+        3.0 * (1.0 - s).powi(2) * s * p1 + 3.0 * (1.0 - s) * s * s * p2 + s.powi(3)
+    }
+    fn bezier_slope(p1: f32, p2: f32, s: f32) -> f32 {
+        3.0 * (1.0 - s).powi(2) * p1 + 6.0 * (1.0 - s) * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+    }
+
+    // Find the curve parameter for `x` with Newton's method, falling back to bisection when the
+    // slope is too flat.
+    let mut s = x;
+    for _ in 0..8 {
+        let slope = bezier_slope(x1, x2, s);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        let error = bezier(x1, x2, s) - x;
+        if error.abs() < 1e-6 {
+            return bezier(y1, y2, s);
+        }
+        s -= error / slope;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        let value = bezier(x1, x2, s);
+        if (value - x).abs() < 1e-6 {
+            break;
+        }
+        if value < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    bezier(y1, y2, s)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Color, Easing, EasingMode};
+
+    const MODES: [EasingMode; 3] = [EasingMode::In, EasingMode::Out, EasingMode::InOut];
+
+    fn all() -> Vec<Easing> {
+        let mut easings = vec![
+            Easing::Linear,
+            Easing::Hold,
+            Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+            Easing::CubicBezier(0.68, -0.6, 0.32, 1.6),
+            Easing::Steps(4),
+        ];
+        for mode in MODES {
+            easings.extend([
+                Easing::Quad(mode),
+                Easing::Cubic(mode),
+                Easing::Quart(mode),
+                Easing::Quint(mode),
+                Easing::Sine(mode),
+                Easing::Expo(mode),
+                Easing::Circ(mode),
+                Easing::Back(mode),
+                Easing::Elastic(mode),
+                Easing::Bounce(mode),
+            ]);
+        }
+        easings
+    }
+
+    #[test]
+    fn test_endpoints() {
+        for easing in all() {
+            assert!(easing.apply(0.0).abs() < 1e-4, "{easing:?}");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-4, "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn test_monotonic() {
+        let mut easings = vec![
+            Easing::Linear,
+            Easing::Hold,
+            Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+            Easing::Steps(4),
+        ];
+        for mode in MODES {
+            easings.extend([
+                Easing::Quad(mode),
+                Easing::Cubic(mode),
+                Easing::Quart(mode),
+                Easing::Quint(mode),
+                Easing::Sine(mode),
+                Easing::Expo(mode),
+                Easing::Circ(mode),
+            ]);
+        }
+        for easing in easings {
+            let mut previous = easing.apply(0.0);
+            for i in 1..=100 {
+                let value = easing.apply(i as f32 / 100.0);
+                assert!(value >= previous - 1e-6, "{easing:?} at {i}");
+                previous = value;
+            }
+        }
+    }
+
+    #[test]
+    fn test_values() {
+        assert_eq!(Easing::Quad(EasingMode::In).apply(0.5), 0.25);
+        assert_eq!(Easing::Quad(EasingMode::Out).apply(0.5), 0.75);
+        assert_eq!(Easing::Cubic(EasingMode::InOut).apply(0.25), 0.0625);
+        assert_eq!(Easing::Steps(4).apply(0.3), 0.25);
+        assert!((Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3) - 0.3).abs() < 1e-4);
+        assert!(Easing::Back(EasingMode::In).apply(0.2) < 0.0);
+        assert_eq!(
+            Easing::Linear.interpolate(&Color::new(1.0, 0.0, 0.0), &Color::new(0.0, 0.0, 1.0), 0.5),
+            Color::new(0.5, 0.0, 0.5)
+        );
+    }
+}
@@ -1,27 +1,36 @@
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use crate::{Color, FPS_RANGE, PolychromaticError, defs, device::Device};
+use crate::{
+    Color, EffectWriter, FPS_RANGE, PolychromaticError, Rgba, defs,
+    device::{Device, KeyboardLayout},
+};
 
-#[derive(Debug)]
-pub struct EffectMatrix {
+/// Grid of values for each key, holds [`Color`] unless used as a layer or mask
+#[derive(Debug, Clone)]
+pub struct EffectMatrix<T = Color> {
     width: u32,
     #[allow(unused)]
     height: u32,
-    values: Box<[Color]>,
+    values: Box<[T]>,
 }
 
-impl EffectMatrix {
-    fn new(width: u32, height: u32) -> Self {
+impl<T: Clone + Default> EffectMatrix<T> {
+    pub fn new(width: u32, height: u32) -> Self {
         Self {
             width,
             height,
-            values: vec![Color::default(); width as usize * height as usize].into_boxed_slice(),
+            values: vec![T::default(); width as usize * height as usize].into_boxed_slice(),
         }
     }
+}
 
+impl<T> EffectMatrix<T> {
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -37,29 +46,29 @@ impl EffectMatrix {
         Some(x as usize + y as usize * self.width as usize)
     }
 
-    pub fn get(&self, x: u32, y: u32) -> Option<&Color> {
+    pub fn get(&self, x: u32, y: u32) -> Option<&T> {
         self.values.get(self.pos_to_index(x, y)?)
     }
 
-    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut Color> {
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut T> {
         self.values.get_mut(self.pos_to_index(x, y)?)
     }
 
-    pub fn set(&mut self, color: Color, x: u32, y: u32) {
+    pub fn set(&mut self, color: T, x: u32, y: u32) {
         if let Some(matrix_color) = self.get_mut(x, y) {
             *matrix_color = color;
         }
     }
 
-    pub fn values(&self) -> &[Color] {
+    pub fn values(&self) -> &[T] {
         &self.values
     }
 
-    pub fn values_mut(&mut self) -> &mut [Color] {
+    pub fn values_mut(&mut self) -> &mut [T] {
         &mut self.values
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (u32, u32, &Color)> {
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32, &T)> {
         self.values.iter().enumerate().map(|(i, c)| {
             (
                 (i % self.width as usize) as u32,
@@ -69,7 +78,7 @@ impl EffectMatrix {
         })
     }
 
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = (u32, u32, &mut Color)> {
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (u32, u32, &mut T)> {
         self.values.iter_mut().enumerate().map(|(i, c)| {
             (
                 (i % self.width as usize) as u32,
@@ -80,6 +89,111 @@ impl EffectMatrix {
     }
 }
 
+impl EffectMatrix {
+    /// Composite a translucent `color` on top of the key at `x`, `y`
+    pub fn paint(&mut self, color: Rgba, x: u32, y: u32) {
+        if let Some(backdrop) = self.get_mut(x, y) {
+            *backdrop = color.over_color(backdrop);
+        }
+    }
+}
+
+/// When two colors are considered different enough to be re-encoded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeltaThreshold {
+    /// Differ once quantized to 8 bits per component
+    Quantized,
+    /// Differ by more than the given [`Color::distance`]
+    Distance(f32),
+    /// Differ by more than the given [`Color::delta_e`]
+    Perceptual(f32),
+}
+
+impl DeltaThreshold {
+    pub fn differs(&self, a: &Color, b: &Color) -> bool {
+        match self {
+            Self::Quantized => a.to_quantized() != b.to_quantized(),
+            Self::Distance(distance) => a.distance(b) > *distance,
+            Self::Perceptual(delta_e) => a.delta_e(b) > *delta_e,
+        }
+    }
+}
+
+/// How frames are written by [`Effect::to_effect_json`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FrameEncoding {
+    /// The first frame writes every key, later frames all of their non-black keys and keys that
+    /// turned black
+    #[default]
+    Full,
+    /// Every frame only writes keys that changed from the previous frame, including keys that
+    /// turned black
+    Delta(DeltaThreshold),
+}
+
+/// Time of a frame rendered with [`Effect::render`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTime {
+    pub index: usize,
+    /// Number of frames rendered
+    pub count: usize,
+    /// Normalized position in the animation, when looping `1.0` is never reached as it would
+    /// repeat the first frame
+    pub phase: f32,
+    /// Seconds since the start of the animation
+    pub seconds: f32,
+}
+
+/// Tracks the keys written so far to encode frames with [`FrameEncoding`]
+#[derive(Debug)]
+pub(crate) struct FrameEncoder {
+    encoding: FrameEncoding,
+    previous: Option<Vec<Color>>,
+}
+
+impl FrameEncoder {
+    pub(crate) fn new(encoding: FrameEncoding) -> Self {
+        Self {
+            encoding,
+            previous: None,
+        }
+    }
+
+    pub(crate) fn encode(&mut self, frame: &EffectMatrix) -> defs::EffectFrame {
+        let mut cols: BTreeMap<u32, BTreeMap<u32, String>> = BTreeMap::new();
+        let mut insert = |x: u32, y: u32, color: &Color| {
+            cols.entry(x).or_default().insert(y, color.to_hex());
+        };
+        match (self.encoding, &mut self.previous) {
+            // The first frame writes every key, so playback starts from a known state even when
+            // looping back from the last frame.
+            (_, previous @ None) => {
+                frame.iter().for_each(|(x, y, color)| insert(x, y, color));
+                *previous = Some(frame.values().to_vec());
+            }
+            // Keys missing from a frame keep their previous color, so keys that turned black are
+            // written too.
+            (FrameEncoding::Full, Some(previous)) => {
+                frame
+                    .iter()
+                    .zip(previous.iter())
+                    .filter(|((_, _, color), previous)| !color.is_black() || !previous.is_black())
+                    .for_each(|((x, y, color), _)| insert(x, y, color));
+                previous.copy_from_slice(frame.values());
+            }
+            (FrameEncoding::Delta(threshold), Some(previous)) => frame
+                .iter()
+                .zip(previous.iter_mut())
+                .filter(|((_, _, color), previous)| threshold.differs(color, previous))
+                .for_each(|((x, y, color), previous)| {
+                    insert(x, y, color);
+                    *previous = *color;
+                }),
+        }
+        defs::EffectFrame { cols }
+    }
+}
+
 #[derive(Debug)]
 pub struct Effect {
     pub name: String,
@@ -89,6 +203,11 @@ pub struct Effect {
     device: Device,
     fps: u32,
     pub r#loop: bool,
+    pub encoding: FrameEncoding,
+    /// Layout of the graphic shown in the polychromatic editor
+    pub layout: KeyboardLayout,
+    /// Write indented JSON
+    pub pretty: bool,
     width: u32,
     height: u32,
     frames: Vec<EffectMatrix>,
@@ -107,12 +226,19 @@ impl Effect {
             device,
             fps: 1,
             r#loop: true,
+            encoding: FrameEncoding::default(),
+            layout: KeyboardLayout::default(),
+            pretty: false,
             width,
             height,
             frames: Vec::new(),
         })
     }
 
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
     pub fn set_fps(&mut self, fps: u32) -> Result<(), PolychromaticError> {
         if !FPS_RANGE.contains(&fps) {
             return Err(PolychromaticError::InvalidFPS(fps));
@@ -146,10 +272,48 @@ impl Effect {
         self.frames.last_mut().unwrap()
     }
 
-    pub fn to_effect_json(&self) -> Result<String, PolychromaticError> {
-        // TODO: Each frame don't encode pixels that have a very slight or no visual difference
-        // from the last frame.
-        Ok(serde_json::to_string(&defs::Effect {
+    /// Number of frames needed to last `duration` at the current FPS, at least 1
+    pub fn frame_count(&self, duration: Duration) -> usize {
+        ((duration.as_secs_f64() * self.fps as f64).round() as usize).max(1)
+    }
+
+    /// Append frames lasting `duration`, calling `render` for each new frame
+    pub fn render<F: FnMut(FrameTime, &mut EffectMatrix)>(
+        &mut self,
+        duration: Duration,
+        mut render: F,
+    ) {
+        let count = self.frame_count(duration);
+        let phase_count = match (self.r#loop, count) {
+            (true, _) | (false, 1) => count,
+            (false, _) => count - 1,
+        };
+        for index in 0..count {
+            let time = FrameTime {
+                index,
+                count,
+                phase: index as f32 / phase_count as f32,
+                seconds: index as f32 / self.fps as f32,
+            };
+            render(time, self.new_frame());
+        }
+    }
+
+    /// Append frames lasting `duration`, calling `render` for each key of each new frame
+    pub fn render_pixels<F: FnMut(u32, u32, FrameTime) -> Color>(
+        &mut self,
+        duration: Duration,
+        mut render: F,
+    ) {
+        self.render(duration, |time, frame| {
+            frame
+                .iter_mut()
+                .for_each(|(x, y, color)| *color = render(x, y, time));
+        });
+    }
+
+    pub(crate) fn header(&self) -> Result<defs::EffectHeader, PolychromaticError> {
+        Ok(defs::EffectHeader {
             name: self.name.clone(),
             author: self.author.clone(),
             icon: self.icon.clone().canonicalize()?,
@@ -157,36 +321,266 @@ impl Effect {
             r#type: 3,
             map_device: self.device.to_string(),
             map_device_icon: self.device.icon().to_owned(),
-            // TODO: Implement.
-            map_graphic: "blackwidow_v3_en_US.svg".to_owned(),
+            // Polychromatic falls back to a plain grid without a graphic.
+            map_graphic: self.device.map_graphic(self.layout).unwrap_or_default(),
             map_cols: self.width,
             map_rows: self.height,
             save_format: 8,
             revision: 1,
             fps: self.fps,
             r#loop: self.r#loop,
-            frames: self
-                .frames()
-                .iter()
-                .map(|frame| {
-                    let mut cols: HashMap<String, HashMap<String, String>> = HashMap::new();
-                    frame
-                        .iter()
-                        .filter(|(_, _, color)| !color.is_black())
-                        .for_each(|(row, col, color)| {
-                            cols.entry(row.to_string())
-                                .or_default()
-                                .insert(col.to_string(), color.to_hex());
-                        });
-                    defs::EffectFrame { cols }
-                })
-                .collect(),
-        })?)
+        })
+    }
+
+    /// Identical effects always give identical output, keys are written in numeric order
+    pub fn to_effect_json(&self) -> Result<String, PolychromaticError> {
+        let mut writer = EffectWriter::new(Vec::new(), self)?;
+        for frame in &self.frames {
+            writer.write_frame(frame)?;
+        }
+        Ok(String::from_utf8(writer.finish()?).expect("serde_json only writes UTF-8"))
     }
 
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PolychromaticError> {
-        let str = self.to_effect_json()?;
-        std::fs::write(path, &str)?;
+        let mut writer = EffectWriter::new(BufWriter::new(File::create(path)?), self)?;
+        for frame in &self.frames {
+            writer.write_frame(frame)?;
+        }
+        writer.finish()?.flush()?;
         Ok(())
     }
+
+    /// Parse a polychromatic sequence effect
+    ///
+    /// Keys missing from a frame keep their color from the previous frame.
+    pub fn from_effect_json(str: &str) -> Result<Self, PolychromaticError> {
+        let defs::Effect::<String> {
+            header: def,
+            frames,
+        } = serde_json::from_str(str)?;
+
+        if def.r#type != 3 {
+            return Err(PolychromaticError::UnsupportedEffectType(def.r#type, 3));
+        }
+
+        let device = Device::from_map(&def.map_device, def.map_cols, def.map_rows)?;
+
+        let mut effect = Self::new(device, &def.icon)?;
+        effect.name = def.name;
+        effect.author = def.author;
+        effect.summary = def.summary;
+        effect.set_fps(def.fps)?;
+        effect.r#loop = def.r#loop;
+        if let Some(layout) = KeyboardLayout::from_map_graphic(&def.map_graphic) {
+            effect.layout = layout;
+        }
+
+        for (index, frame_def) in frames.into_iter().enumerate() {
+            let malformed = |reason: String| PolychromaticError::MalformedFrame {
+                frame: index,
+                reason,
+            };
+
+            let previous = effect.frames.last().cloned();
+            let frame = effect.new_frame();
+            if let Some(previous) = previous {
+                *frame = previous;
+            }
+            for (x_str, col) in frame_def.cols {
+                let x = x_str
+                    .parse::<u32>()
+                    .map_err(|_| malformed(format!("Invalid column {x_str:?}")))?;
+                for (y_str, hex) in col {
+                    let y = y_str
+                        .parse::<u32>()
+                        .map_err(|_| malformed(format!("Invalid row {y_str:?}")))?;
+                    let color = Color::from_hex(&hex)
+                        .ok_or_else(|| malformed(format!("Invalid color {hex:?} at {x}, {y}")))?;
+                    *frame
+                        .get_mut(x, y)
+                        .ok_or_else(|| malformed(format!("Position {x}, {y} out of bounds")))? =
+                        color;
+                }
+            }
+        }
+
+        Ok(effect)
+    }
+
+    /// Load a polychromatic sequence effect file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PolychromaticError> {
+        let str = std::fs::read_to_string(path)?;
+        Self::from_effect_json(&str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        Color, DeltaThreshold, Device, Effect, FrameEncoding, Keyboard, PolychromaticError,
+    };
+
+    #[test]
+    fn test_load() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.name = "Test".to_owned();
+        effect.set_fps(12).unwrap();
+        effect
+            .new_frame()
+            .set(Color::from_hex("#FF8000").unwrap(), 3, 4);
+        effect
+            .new_frame()
+            .set(Color::from_hex("#0080FF").unwrap(), 14, 0);
+
+        let loaded = Effect::from_effect_json(&effect.to_effect_json().unwrap()).unwrap();
+        assert_eq!(loaded.name, "Test");
+        assert_eq!(*loaded.device(), *effect.device());
+        assert_eq!(loaded.frames().len(), 2);
+        for (frame, loaded_frame) in effect.frames().iter().zip(loaded.frames()) {
+            assert_eq!(frame.values(), loaded_frame.values());
+        }
+
+        let str = r##"{"name":"","type":3,"map_device":"Razer Huntsman Mini","map_cols":15,"map_rows":5,"fps":1,"loop":true,"frames":[{"15":{"0":"#FFFFFF"}}]}"##;
+        assert!(matches!(
+            Effect::from_effect_json(str),
+            Err(PolychromaticError::MalformedFrame { frame: 0, .. })
+        ));
+        for frames in [r##"{"a":{"0":"#FFFFFF"}}"##, r##"{"0":{"-1":"#FFFFFF"}}"##] {
+            assert!(matches!(
+                Effect::from_effect_json(&str.replace(r##"{"15":{"0":"#FFFFFF"}}"##, frames)),
+                Err(PolychromaticError::MalformedFrame { frame: 0, .. })
+            ));
+        }
+        let str = str.replace("Razer Huntsman Mini", "Razer Nonexistent");
+        assert!(matches!(
+            Effect::from_effect_json(&str),
+            Err(PolychromaticError::UnknownDevice(_))
+        ));
+    }
+
+    #[test]
+    fn test_full_encoding() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.new_frame().set(Color::new(1.0, 1.0, 1.0), 2, 1);
+        effect.new_frame();
+        effect.new_frame();
+
+        let json = effect.to_effect_json().unwrap();
+        // The first frame blacks out keys left lit when looping back from the last frame.
+        let first = &serde_json::from_str::<serde_json::Value>(&json).unwrap()["frames"][0];
+        let first = first.as_object().unwrap();
+        assert_eq!(first.len(), 15);
+        assert!(
+            first
+                .values()
+                .all(|col| col.as_object().unwrap().len() == 5)
+        );
+        assert_eq!(first["2"]["1"], "#FFFFFF");
+        assert_eq!(first["3"]["1"], "#000000");
+        assert!(json.ends_with(r##"},{"2":{"1":"#000000"}},{}]}"##));
+        let loaded = Effect::from_effect_json(&json).unwrap();
+        for (frame, loaded_frame) in effect.frames().iter().zip(loaded.frames()) {
+            assert_eq!(frame.values(), loaded_frame.values());
+        }
+    }
+
+    #[test]
+    fn test_delta_encoding() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        for i in 0..30 {
+            let frame = effect.new_frame();
+            frame.iter_mut().for_each(|(x, y, color)| {
+                *color = if (x + y + i) % 7 == 0 {
+                    Color::from_quantized(255, 0, 0)
+                } else {
+                    Color::from_quantized(0, (x * 10) as u8, (y * 20) as u8)
+                };
+            });
+        }
+
+        let full = effect.to_effect_json().unwrap();
+        effect.encoding = FrameEncoding::Delta(DeltaThreshold::Quantized);
+        let delta = effect.to_effect_json().unwrap();
+        assert!(delta.len() < full.len());
+
+        let loaded = Effect::from_effect_json(&delta).unwrap();
+        for (frame, loaded_frame) in effect.frames().iter().zip(loaded.frames()) {
+            assert_eq!(frame.values(), loaded_frame.values());
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        fn effect() -> Effect {
+            let mut effect = Effect::new(
+                Device::Keyboard(Keyboard::RazerBlackWidowV3),
+                "effects/pride.png",
+            )
+            .unwrap();
+            for i in 0..5 {
+                effect.new_frame().iter_mut().for_each(|(x, y, color)| {
+                    *color = Color::from_hsl((x * 15 + y * 30 + i * 7) as f32, 1.0, 0.5);
+                });
+            }
+            effect
+        }
+
+        let json = effect().to_effect_json().unwrap();
+        assert_eq!(json, effect().to_effect_json().unwrap());
+
+        // Columns and rows are in numeric order, not string order.
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerBlackWidowV3),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.new_frame();
+        let frame = effect.new_frame();
+        let white = Color::new(1.0, 1.0, 1.0);
+        for (x, y) in [(10, 0), (2, 5), (2, 1), (10, 3)] {
+            frame.set(white, x, y);
+        }
+        let json = effect.to_effect_json().unwrap();
+        assert!(json.ends_with(
+            r##"},{"2":{"1":"#FFFFFF","5":"#FFFFFF"},"10":{"0":"#FFFFFF","3":"#FFFFFF"}}]}"##
+        ));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.set_fps(30).unwrap();
+
+        let mut times = Vec::new();
+        effect.render(Duration::from_secs(2), |time, _| times.push(time));
+        assert_eq!(effect.frames().len(), 60);
+        assert_eq!(times[0].phase, 0.0);
+        assert_eq!(times[30].phase, 0.5);
+        assert_eq!(times[30].seconds, 1.0);
+        assert!(times[59].phase < 1.0);
+
+        effect.r#loop = false;
+        times.clear();
+        effect.render(Duration::from_millis(500), |time, _| times.push(time));
+        assert_eq!(times.len(), 15);
+        assert_eq!(times[14].phase, 1.0);
+    }
 }
@@ -0,0 +1,303 @@
+//! Manages the effects polychromatic loads from its config directory
+
+use std::path::{Path, PathBuf};
+
+use crate::{Effect, LayeredEffect, PolychromaticError, ScriptedEffect, defs};
+
+/// Effects that can be installed into an [`EffectLibrary`]
+pub trait LibraryEffect {
+    fn name(&self) -> &str;
+    fn icon_mut(&mut self) -> &mut PathBuf;
+    fn save(&self, path: &Path) -> Result<(), PolychromaticError>;
+}
+
+impl LibraryEffect for Effect {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn icon_mut(&mut self) -> &mut PathBuf {
+        &mut self.icon
+    }
+
+    fn save(&self, path: &Path) -> Result<(), PolychromaticError> {
+        Effect::save(self, path)
+    }
+}
+
+impl LibraryEffect for LayeredEffect {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn icon_mut(&mut self) -> &mut PathBuf {
+        &mut self.icon
+    }
+
+    fn save(&self, path: &Path) -> Result<(), PolychromaticError> {
+        LayeredEffect::save(self, path)
+    }
+}
+
+impl LibraryEffect for ScriptedEffect {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn icon_mut(&mut self) -> &mut PathBuf {
+        &mut self.icon
+    }
+
+    fn save(&self, path: &Path) -> Result<(), PolychromaticError> {
+        ScriptedEffect::save(self, path)
+    }
+}
+
+/// Effect file found in an [`EffectLibrary`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstalledEffect {
+    pub path: PathBuf,
+    pub name: String,
+    pub r#type: i64,
+    pub author: String,
+    pub icon: PathBuf,
+    pub summary: String,
+    pub map_device: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectLibrary {
+    dir: PathBuf,
+}
+
+impl EffectLibrary {
+    /// Use `dir` as the effects directory
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Use polychromatic's effects directory, `$XDG_CONFIG_HOME/polychromatic/effects` or
+    /// `$HOME/.config/polychromatic/effects`
+    pub fn open() -> Result<Self, PolychromaticError> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(PathBuf::from)
+                    .filter(|dir| dir.is_absolute())
+                    .map(|home| home.join(".config"))
+            })
+            .ok_or(PolychromaticError::NoConfigDirectory)?;
+        Ok(Self::new(config_dir.join("polychromatic").join("effects")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn effect_path(&self, name: &str) -> Result<PathBuf, PolychromaticError> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\0']) {
+            return Err(PolychromaticError::InvalidEffectName(name.to_owned()));
+        }
+        Ok(self.dir.join(format!("{name}.json")))
+    }
+
+    /// Save `effect` into the library, copying its icon next to it. Replaces any installed
+    /// effect with the same name.
+    pub fn install<E: LibraryEffect>(&self, effect: &mut E) -> Result<PathBuf, PolychromaticError> {
+        let path = self.effect_path(effect.name())?;
+        std::fs::create_dir_all(&self.dir)?;
+
+        let icon = effect.icon_mut().clone();
+        let installed_icon = match icon.extension() {
+            Some(extension) => path.with_extension(extension),
+            None => path.with_extension("icon"),
+        };
+        if icon.canonicalize()? != installed_icon.canonicalize().unwrap_or_default() {
+            std::fs::copy(&icon, &installed_icon)?;
+        }
+
+        *effect.icon_mut() = installed_icon;
+        let result = effect.save(&path);
+        *effect.icon_mut() = icon;
+        result?;
+
+        Ok(path)
+    }
+
+    /// Every effect in the library, sorted by file name. Files that aren't effects are skipped.
+    pub fn list(&self) -> Result<Vec<InstalledEffect>, PolychromaticError> {
+        let mut effects = Vec::new();
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(effects),
+            Err(err) => return Err(err.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            // Other JSON files in the directory aren't effects.
+            let Ok(metadata) =
+                serde_json::from_slice::<defs::EffectMetadata>(&std::fs::read(&path)?)
+            else {
+                continue;
+            };
+            effects.push(InstalledEffect {
+                path,
+                name: metadata.name,
+                r#type: metadata.r#type,
+                author: metadata.author,
+                icon: metadata.icon,
+                summary: metadata.summary,
+                map_device: metadata.map_device,
+            });
+        }
+        effects.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(effects)
+    }
+
+    /// Files belonging to an installed effect: the effect itself, its script and an icon
+    /// installed next to it. The icon is left out when the effect can't be parsed.
+    fn effect_files(&self, name: &str) -> Result<(PathBuf, Vec<PathBuf>), PolychromaticError> {
+        let path = self.effect_path(name)?;
+        if !path.is_file() {
+            return Err(PolychromaticError::EffectNotInstalled(name.to_owned()));
+        }
+        let metadata = serde_json::from_slice::<defs::EffectMetadata>(&std::fs::read(&path)?);
+
+        let dir = self.dir.canonicalize()?;
+        let mut files = Vec::new();
+        let script = path.with_extension("py");
+        if script.is_file() {
+            files.push(script);
+        }
+        if let Ok(metadata) = metadata
+            && metadata.icon.parent() == Some(dir.as_path())
+            && metadata.icon.file_stem() == path.file_stem()
+            && metadata.icon.is_file()
+        {
+            files.push(metadata.icon);
+        }
+        Ok((path, files))
+    }
+
+    /// Rename an installed effect, along with its script and installed icon
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<PathBuf, PolychromaticError> {
+        let (path, files) = self.effect_files(name)?;
+        let new_path = self.effect_path(new_name)?;
+        let moves = files
+            .into_iter()
+            .map(|file| {
+                let new_file = file.with_file_name(format!("{new_name}.json"));
+                let new_file = match file.extension() {
+                    Some(extension) => new_file.with_extension(extension),
+                    None => new_file.with_extension(""),
+                };
+                (file, new_file)
+            })
+            .collect::<Vec<_>>();
+        // Nothing is touched when any file of the new name belongs to another effect.
+        if new_path.exists() || moves.iter().any(|(_, new_file)| new_file.exists()) {
+            return Err(PolychromaticError::EffectAlreadyInstalled(
+                new_name.to_owned(),
+            ));
+        }
+
+        let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        json["name"] = new_name.into();
+        for (file, new_file) in &moves {
+            if json["icon"].as_str() == file.to_str() {
+                json["icon"] = new_file.to_string_lossy().into_owned().into();
+            }
+        }
+        std::fs::write(&new_path, serde_json::to_string(&json)?)?;
+        for (file, new_file) in moves {
+            std::fs::rename(file, new_file)?;
+        }
+        std::fs::remove_file(&path)?;
+
+        Ok(new_path)
+    }
+
+    /// Remove an installed effect, along with its script and installed icon
+    pub fn remove(&self, name: &str) -> Result<(), PolychromaticError> {
+        let (path, files) = self.effect_files(name)?;
+        for file in files {
+            std::fs::remove_file(file)?;
+        }
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Device, Effect, EffectLibrary, Keyboard, PolychromaticError};
+
+    #[test]
+    fn test_library() {
+        let dir =
+            std::env::temp_dir().join(format!("polychromatic-library-{}", std::process::id()));
+        let library = EffectLibrary::new(&dir);
+        assert!(library.list().unwrap().is_empty());
+
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.name = "Pride".to_owned();
+        effect.new_frame();
+        let path = library.install(&mut effect).unwrap();
+        assert_eq!(path, dir.join("Pride.json"));
+        assert!(dir.join("Pride.png").is_file());
+        assert_eq!(effect.icon.to_str(), Some("effects/pride.png"));
+
+        std::fs::write(dir.join("notes.json"), "[]").unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+        let installed = library.list().unwrap();
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].name, "Pride");
+        assert_eq!(installed[0].r#type, 3);
+        assert_eq!(installed[0].icon, dir.join("Pride.png"));
+
+        std::fs::write(dir.join("Rainbow.py"), "").unwrap();
+        std::fs::write(dir.join("Pride.py"), "").unwrap();
+        assert!(matches!(
+            library.rename("Pride", "Rainbow"),
+            Err(PolychromaticError::EffectAlreadyInstalled(_))
+        ));
+        assert!(dir.join("Pride.json").is_file() && !dir.join("Rainbow.json").exists());
+        std::fs::remove_file(dir.join("Rainbow.py")).unwrap();
+
+        library.rename("Pride", "Rainbow").unwrap();
+        assert!(dir.join("Rainbow.py").is_file());
+        assert!(!dir.join("Pride.json").exists());
+        assert!(!dir.join("Pride.png").exists());
+        let installed = library.list().unwrap();
+        assert_eq!(installed[0].name, "Rainbow");
+        assert_eq!(installed[0].icon, dir.join("Rainbow.png"));
+
+        assert!(matches!(
+            library.remove("../Rainbow"),
+            Err(PolychromaticError::InvalidEffectName(_))
+        ));
+        library.remove("Rainbow").unwrap();
+        assert!(library.list().unwrap().is_empty());
+        std::fs::remove_file(dir.join("notes.json")).unwrap();
+
+        // Effects that can't be parsed can still be removed, but not renamed.
+        std::fs::write(dir.join("broken.py"), "").unwrap();
+        assert!(library.rename("broken", "fixed").is_err());
+        assert!(dir.join("broken.json").is_file() && dir.join("broken.py").is_file());
+        library.remove("broken").unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use std::io::Write;
+
+use crate::{Effect, EffectMatrix, FrameEncoder, PolychromaticError, defs};
+
+/// Writes a sequence effect one frame at a time, producing the same output as
+/// [`Effect::to_effect_json`] without keeping every frame in memory
+#[derive(Debug)]
+pub struct EffectWriter<W: Write> {
+    writer: W,
+    encoder: FrameEncoder,
+    pretty: bool,
+    width: u32,
+    height: u32,
+    frames: usize,
+}
+
+impl<W: Write> EffectWriter<W> {
+    /// Write the header of `effect`, its frames are ignored
+    pub fn new(mut writer: W, effect: &Effect) -> Result<Self, PolychromaticError> {
+        let header = defs::to_json(&effect.header()?, effect.pretty)?;
+        // Reopen the header object to append the frames to it.
+        if effect.pretty {
+            writer.write_all(header.trim_end_matches("\n}").as_bytes())?;
+            writer.write_all(b",\n  \"frames\": [")?;
+        } else {
+            writer.write_all(header.trim_end_matches('}').as_bytes())?;
+            writer.write_all(b",\"frames\":[")?;
+        }
+        Ok(Self {
+            writer,
+            encoder: FrameEncoder::new(effect.encoding),
+            pretty: effect.pretty,
+            width: effect.width(),
+            height: effect.height(),
+            frames: 0,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of frames written so far
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Blank frame matching the size of the effect
+    pub fn new_frame(&self) -> EffectMatrix {
+        EffectMatrix::new(self.width, self.height)
+    }
+
+    pub fn write_frame(&mut self, frame: &EffectMatrix) -> Result<(), PolychromaticError> {
+        if (frame.width(), frame.height()) != (self.width, self.height) {
+            return Err(PolychromaticError::FrameSizeMismatch(
+                frame.width(),
+                frame.height(),
+                self.width,
+                self.height,
+            ));
+        }
+        if self.frames > 0 {
+            self.writer.write_all(b",")?;
+        }
+        let frame = defs::to_json(&self.encoder.encode(frame), self.pretty)?;
+        if self.pretty {
+            // Frames are nested two levels deep.
+            self.writer.write_all(b"\n    ")?;
+            self.writer
+                .write_all(frame.replace('\n', "\n    ").as_bytes())?;
+        } else {
+            self.writer.write_all(frame.as_bytes())?;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Close the effect and return the underlying writer
+    pub fn finish(mut self) -> Result<W, PolychromaticError> {
+        match (self.pretty, self.frames) {
+            (false, _) => self.writer.write_all(b"]}")?,
+            (true, 0) => self.writer.write_all(b"]\n}")?,
+            (true, _) => self.writer.write_all(b"\n  ]\n}")?,
+        }
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Color, DeltaThreshold, Device, Effect, EffectWriter, FrameEncoder, FrameEncoding, Keyboard,
+        defs,
+    };
+
+    #[test]
+    fn test_effect_writer() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerBlackWidowV3),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.encoding = FrameEncoding::Delta(DeltaThreshold::Quantized);
+
+        let mut writer = EffectWriter::new(Vec::new(), &effect).unwrap();
+        for i in 0..20 {
+            let mut frame = writer.new_frame();
+            frame.iter_mut().for_each(|(x, y, color)| {
+                *color = Color::from_hsl((x * 10 + y * 5 + i * 3) as f32, 1.0, 0.5);
+            });
+            writer.write_frame(&frame).unwrap();
+            *effect.new_frame() = frame;
+        }
+        let streamed = writer.finish().unwrap();
+
+        assert_eq!(streamed, effect.to_effect_json().unwrap().into_bytes());
+    }
+
+    #[test]
+    fn test_pretty() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.pretty = true;
+        assert_eq!(
+            effect.to_effect_json().unwrap(),
+            serde_json::to_string_pretty(&defs::Effect::<u32> {
+                header: effect.header().unwrap(),
+                frames: Vec::new(),
+            })
+            .unwrap()
+        );
+
+        effect
+            .new_frame()
+            .set(Color::from_quantized(255, 0, 0), 11, 2);
+        effect
+            .new_frame()
+            .set(Color::from_quantized(0, 0, 255), 2, 3);
+        let mut frames = Vec::new();
+        let mut encoder = FrameEncoder::new(effect.encoding);
+        for frame in effect.frames() {
+            frames.push(encoder.encode(frame));
+        }
+        assert_eq!(
+            effect.to_effect_json().unwrap(),
+            serde_json::to_string_pretty(&defs::Effect {
+                header: effect.header().unwrap(),
+                frames,
+            })
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,680 @@
+//! Bitmap fonts and scrolling text
+
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use crate::{Color, Effect, EffectMatrix, PolychromaticError};
+
+/// Built-in 3x5 font, lowercase letters are drawn as uppercase
+#[rustfmt::skip]
+const FONT_3X5: &[(char, [u8; 5])] = &[
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('"', [0b101, 0b101, 0b000, 0b000, 0b000]),
+    ('#', [0b101, 0b111, 0b101, 0b111, 0b101]),
+    ('$', [0b011, 0b110, 0b010, 0b011, 0b110]),
+    ('%', [0b100, 0b001, 0b010, 0b100, 0b001]),
+    ('&', [0b010, 0b101, 0b010, 0b101, 0b011]),
+    ('\'', [0b010, 0b010, 0b000, 0b000, 0b000]),
+    ('(', [0b001, 0b010, 0b010, 0b010, 0b001]),
+    (')', [0b100, 0b010, 0b010, 0b010, 0b100]),
+    ('*', [0b000, 0b101, 0b010, 0b101, 0b000]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b011, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    (';', [0b000, 0b010, 0b000, 0b010, 0b100]),
+    ('<', [0b001, 0b010, 0b100, 0b010, 0b001]),
+    ('=', [0b000, 0b111, 0b000, 0b111, 0b000]),
+    ('>', [0b100, 0b010, 0b001, 0b010, 0b100]),
+    ('?', [0b111, 0b001, 0b011, 0b000, 0b010]),
+    ('@', [0b010, 0b101, 0b111, 0b100, 0b011]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b111, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b011]),
+    ('V', [0b101, 0b101, 0b101, 0b010, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('[', [0b110, 0b100, 0b100, 0b100, 0b110]),
+    ('\\', [0b100, 0b100, 0b010, 0b001, 0b001]),
+    (']', [0b011, 0b001, 0b001, 0b001, 0b011]),
+    ('^', [0b010, 0b101, 0b000, 0b000, 0b000]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('`', [0b100, 0b010, 0b000, 0b000, 0b000]),
+    ('{', [0b011, 0b010, 0b110, 0b010, 0b011]),
+    ('|', [0b010, 0b010, 0b010, 0b010, 0b010]),
+    ('}', [0b110, 0b010, 0b011, 0b010, 0b110]),
+    ('~', [0b000, 0b001, 0b111, 0b100, 0b000]),
+
+];
+
+/// Built-in 5x6 font, capitals and digits are 5 rows tall and descenders use the sixth
+#[rustfmt::skip]
+const FONT_5X6: &[(char, [u8; 6])] = &[
+    (' ', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('!', [0b00100, 0b00100, 0b00100, 0b00000, 0b00100, 0b00000]),
+    ('"', [0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('#', [0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b00000]),
+    ('$', [0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00000]),
+    ('%', [0b11001, 0b11010, 0b00100, 0b01011, 0b10011, 0b00000]),
+    ('&', [0b01100, 0b10010, 0b01101, 0b10010, 0b01101, 0b00000]),
+    ('\'', [0b00100, 0b00100, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('(', [0b00010, 0b00100, 0b00100, 0b00100, 0b00010, 0b00000]),
+    (')', [0b01000, 0b00100, 0b00100, 0b00100, 0b01000, 0b00000]),
+    ('*', [0b00000, 0b10101, 0b01110, 0b10101, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b11111, 0b00100, 0b00000, 0b00000]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b00100, 0b01000]),
+    ('-', [0b00000, 0b00000, 0b01110, 0b00000, 0b00000, 0b00000]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00100, 0b00000]),
+    ('/', [0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000]),
+    ('0', [0b01110, 0b10011, 0b10101, 0b11001, 0b01110, 0b00000]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b01110, 0b00000]),
+    ('2', [0b01110, 0b10001, 0b00110, 0b01000, 0b11111, 0b00000]),
+    ('3', [0b11110, 0b00001, 0b00110, 0b00001, 0b11110, 0b00000]),
+    ('4', [0b10010, 0b10010, 0b11111, 0b00010, 0b00010, 0b00000]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b11110, 0b00000]),
+    ('6', [0b01110, 0b10000, 0b11110, 0b10001, 0b01110, 0b00000]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b00100, 0b00000]),
+    ('8', [0b01110, 0b10001, 0b01110, 0b10001, 0b01110, 0b00000]),
+    ('9', [0b01110, 0b10001, 0b01111, 0b00001, 0b01110, 0b00000]),
+    (':', [0b00000, 0b00100, 0b00000, 0b00100, 0b00000, 0b00000]),
+    (';', [0b00000, 0b00100, 0b00000, 0b00000, 0b00100, 0b01000]),
+    ('<', [0b00010, 0b00100, 0b01000, 0b00100, 0b00010, 0b00000]),
+    ('=', [0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000]),
+    ('>', [0b01000, 0b00100, 0b00010, 0b00100, 0b01000, 0b00000]),
+    ('?', [0b01110, 0b10001, 0b00110, 0b00000, 0b00100, 0b00000]),
+    ('@', [0b01110, 0b10001, 0b10111, 0b10110, 0b10000, 0b01110]),
+    ('A', [0b01110, 0b10001, 0b11111, 0b10001, 0b10001, 0b00000]),
+    ('B', [0b11110, 0b10001, 0b11110, 0b10001, 0b11110, 0b00000]),
+    ('C', [0b01111, 0b10000, 0b10000, 0b10000, 0b01111, 0b00000]),
+    ('D', [0b11110, 0b10001, 0b10001, 0b10001, 0b11110, 0b00000]),
+    ('E', [0b11111, 0b10000, 0b11110, 0b10000, 0b11111, 0b00000]),
+    ('F', [0b11111, 0b10000, 0b11110, 0b10000, 0b10000, 0b00000]),
+    ('G', [0b01111, 0b10000, 0b10111, 0b10001, 0b01110, 0b00000]),
+    ('H', [0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b00000]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b10010, 0b01100, 0b00000]),
+    ('K', [0b10001, 0b10010, 0b11100, 0b10010, 0b10001, 0b00000]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b11111, 0b00000]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10001, 0b10001, 0b00000]),
+    ('N', [0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b00000]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000]),
+    ('P', [0b11110, 0b10001, 0b11110, 0b10000, 0b10000, 0b00000]),
+    ('Q', [0b01110, 0b10001, 0b10101, 0b10010, 0b01101, 0b00000]),
+    ('R', [0b11110, 0b10001, 0b11110, 0b10010, 0b10001, 0b00000]),
+    ('S', [0b01111, 0b10000, 0b01110, 0b00001, 0b11110, 0b00000]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000]),
+    ('W', [0b10001, 0b10001, 0b10101, 0b11011, 0b10001, 0b00000]),
+    ('X', [0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b00000]),
+    ('Y', [0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00000]),
+    ('Z', [0b11111, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000]),
+    ('[', [0b01110, 0b01000, 0b01000, 0b01000, 0b01110, 0b00000]),
+    ('\\', [0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000]),
+    (']', [0b01110, 0b00010, 0b00010, 0b00010, 0b01110, 0b00000]),
+    ('^', [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000]),
+    ('_', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111]),
+    ('`', [0b01000, 0b00100, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('a', [0b00000, 0b00000, 0b01111, 0b10011, 0b01101, 0b00000]),
+    ('b', [0b10000, 0b10000, 0b11110, 0b10001, 0b11110, 0b00000]),
+    ('c', [0b00000, 0b00000, 0b01111, 0b10000, 0b01111, 0b00000]),
+    ('d', [0b00001, 0b00001, 0b01111, 0b10001, 0b01111, 0b00000]),
+    ('e', [0b00000, 0b01110, 0b11111, 0b10000, 0b01111, 0b00000]),
+    ('f', [0b00110, 0b01000, 0b11100, 0b01000, 0b01000, 0b00000]),
+    ('g', [0b00000, 0b00000, 0b01111, 0b10001, 0b01111, 0b11110]),
+    ('h', [0b10000, 0b10000, 0b11110, 0b10001, 0b10001, 0b00000]),
+    ('i', [0b00100, 0b00000, 0b01100, 0b00100, 0b01110, 0b00000]),
+    ('j', [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b01100]),
+    ('k', [0b10000, 0b10000, 0b10010, 0b11100, 0b10010, 0b00000]),
+    ('l', [0b01100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000]),
+    ('m', [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b00000]),
+    ('n', [0b00000, 0b00000, 0b11110, 0b10001, 0b10001, 0b00000]),
+    ('o', [0b00000, 0b00000, 0b01110, 0b10001, 0b01110, 0b00000]),
+    ('p', [0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000]),
+    ('q', [0b00000, 0b00000, 0b01111, 0b10001, 0b01111, 0b00001]),
+    ('r', [0b00000, 0b00000, 0b10110, 0b11000, 0b10000, 0b00000]),
+    ('s', [0b00000, 0b00000, 0b01111, 0b01110, 0b11110, 0b00000]),
+    ('t', [0b01000, 0b01000, 0b11110, 0b01000, 0b00110, 0b00000]),
+    ('u', [0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00000]),
+    ('v', [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b00000]),
+    ('w', [0b00000, 0b00000, 0b10101, 0b10101, 0b01010, 0b00000]),
+    ('x', [0b00000, 0b00000, 0b11011, 0b00100, 0b11011, 0b00000]),
+    ('y', [0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b11110]),
+    ('z', [0b00000, 0b00000, 0b11111, 0b00100, 0b11111, 0b00000]),
+    ('{', [0b00110, 0b00100, 0b01000, 0b00100, 0b00110, 0b00000]),
+    ('|', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000]),
+    ('}', [0b01100, 0b00100, 0b00010, 0b00100, 0b01100, 0b00000]),
+    ('~', [0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000]),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyph {
+    pub width: u32,
+    pub height: u32,
+    /// Horizontal offset from the pen position
+    pub x_offset: i32,
+    /// Rows from the top of the line to the top of the glyph
+    pub top: i32,
+    /// How far the pen moves after drawing the glyph
+    pub advance: u32,
+    /// Row major, `true` where the glyph is drawn
+    pub bitmap: Vec<bool>,
+}
+
+impl Glyph {
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.bitmap[(x + y * self.width) as usize]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    ascent: u32,
+    descent: u32,
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl BitmapFont {
+    /// Font from a table of rows, the leftmost pixel in the highest bit
+    fn from_table<const H: usize>(
+        table: &[(char, [u8; H])],
+        width: u32,
+        ascent: u32,
+        descent: u32,
+    ) -> Self {
+        let glyphs = table
+            .iter()
+            .map(|(char, rows)| {
+                let glyph = Glyph {
+                    width,
+                    height: H as u32,
+                    x_offset: 0,
+                    top: 0,
+                    advance: width + 1,
+                    bitmap: rows
+                        .iter()
+                        .flat_map(|row| (0..width).rev().map(move |bit| row & (1 << bit) != 0))
+                        .collect(),
+                };
+                (*char, glyph)
+            })
+            .collect();
+        Self {
+            ascent,
+            descent,
+            glyphs,
+        }
+    }
+
+    /// Compact 3x5 font covering printable ASCII
+    pub fn builtin_3x5() -> Self {
+        Self::from_table(FONT_3X5, 3, 5, 0)
+    }
+
+    /// 5x6 font covering printable ASCII with lowercase letters, fills the 6 rows of most
+    /// keyboards
+    pub fn builtin_5x6() -> Self {
+        Self::from_table(FONT_5X6, 5, 5, 1)
+    }
+
+    /// Parse a font in the Glyph Bitmap Distribution Format
+    pub fn from_bdf(str: &str) -> Result<Self, PolychromaticError> {
+        let mut ascent = None;
+        let mut descent = None;
+        let mut bounding_box = None;
+        let mut glyphs = Vec::new();
+
+        let mut lines = str.lines().enumerate();
+        let parse =
+            |line: usize, value: Option<&str>| -> Result<i32, PolychromaticError> {
+                value.and_then(|value| value.parse().ok()).ok_or(
+                    PolychromaticError::CannotParseFont(line + 1, "Expected a number"),
+                )
+            };
+
+        while let Some((index, line)) = lines.next() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("FONT_ASCENT") => ascent = Some(parse(index, words.next())?),
+                Some("FONT_DESCENT") => descent = Some(parse(index, words.next())?),
+                Some("FONTBOUNDINGBOX") => {
+                    let _width = parse(index, words.next())?;
+                    let height = parse(index, words.next())?;
+                    let _x_offset = parse(index, words.next())?;
+                    let y_offset = parse(index, words.next())?;
+                    bounding_box = Some((height, y_offset));
+                }
+                Some("STARTCHAR") => {
+                    let mut encoding = None;
+                    let mut advance = None;
+                    let mut bbx = None;
+                    let mut bitmap = Vec::new();
+                    loop {
+                        let Some((index, line)) = lines.next() else {
+                            return Err(PolychromaticError::CannotParseFont(
+                                index + 1,
+                                "Glyph is missing ENDCHAR",
+                            ));
+                        };
+                        let mut words = line.split_whitespace();
+                        match words.next() {
+                            Some("ENCODING") => encoding = Some(parse(index, words.next())?),
+                            Some("DWIDTH") => advance = Some(parse(index, words.next())?),
+                            Some("BBX") => {
+                                bbx = Some((
+                                    parse(index, words.next())?,
+                                    parse(index, words.next())?,
+                                    parse(index, words.next())?,
+                                    parse(index, words.next())?,
+                                ))
+                            }
+                            Some("BITMAP") => {
+                                let (width, height, _, _) =
+                                    bbx.ok_or(PolychromaticError::CannotParseFont(
+                                        index + 1,
+                                        "BITMAP before BBX",
+                                    ))?;
+                                for _ in 0..height {
+                                    let (index, row) =
+                                        lines.next().ok_or(PolychromaticError::CannotParseFont(
+                                            index + 1,
+                                            "Glyph bitmap is too short",
+                                        ))?;
+                                    // Rows are padded to whole bytes and may be wider than
+                                    // any integer, so bits are read from each hex digit.
+                                    let digits = row
+                                        .trim()
+                                        .chars()
+                                        .map(|digit| digit.to_digit(16))
+                                        .collect::<Option<Vec<_>>>()
+                                        .ok_or(PolychromaticError::CannotParseFont(
+                                            index + 1,
+                                            "Invalid glyph bitmap row",
+                                        ))?;
+                                    if width.max(0) as usize > digits.len() * 4 {
+                                        return Err(PolychromaticError::CannotParseFont(
+                                            index + 1,
+                                            "Glyph bitmap row is narrower than its BBX",
+                                        ));
+                                    }
+                                    bitmap.extend(
+                                        (0..width.max(0) as usize)
+                                            .map(|x| digits[x / 4] & (0b1000 >> (x % 4)) != 0),
+                                    );
+                                }
+                            }
+                            Some("ENDCHAR") => break,
+                            _ => {}
+                        }
+                    }
+                    let (Some(encoding), Some((width, height, x_offset, y_offset))) =
+                        (encoding, bbx)
+                    else {
+                        return Err(PolychromaticError::CannotParseFont(
+                            index + 1,
+                            "Glyph is missing ENCODING or BBX",
+                        ));
+                    };
+                    if bitmap.len() != width.max(0) as usize * height.max(0) as usize {
+                        return Err(PolychromaticError::CannotParseFont(
+                            index + 1,
+                            "Glyph bitmap doesn't match its BBX",
+                        ));
+                    }
+                    // Negative encodings are glyphs without a code point.
+                    let Some(char) = u32::try_from(encoding).ok().and_then(char::from_u32) else {
+                        continue;
+                    };
+                    glyphs.push((
+                        char,
+                        width.max(0) as u32,
+                        height.max(0) as u32,
+                        x_offset,
+                        y_offset,
+                        advance.unwrap_or(width).max(0) as u32,
+                        bitmap,
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let ascent = ascent
+            .or(bounding_box.map(|(height, y_offset)| height + y_offset))
+            .ok_or(PolychromaticError::CannotParseFont(
+                0,
+                "Font is missing FONT_ASCENT and FONTBOUNDINGBOX",
+            ))?;
+        let descent = descent
+            .or(bounding_box.map(|(_, y_offset)| -y_offset))
+            .unwrap_or(0);
+
+        Ok(Self {
+            ascent: ascent.max(0) as u32,
+            descent: descent.max(0) as u32,
+            glyphs: glyphs
+                .into_iter()
+                .map(
+                    |(char, width, height, x_offset, y_offset, advance, bitmap)| {
+                        (
+                            char,
+                            Glyph {
+                                width,
+                                height,
+                                x_offset,
+                                top: ascent - (y_offset + height as i32),
+                                advance,
+                                bitmap,
+                            },
+                        )
+                    },
+                )
+                .collect(),
+        })
+    }
+
+    /// Load a BDF font file
+    pub fn load_bdf<P: AsRef<Path>>(path: P) -> Result<Self, PolychromaticError> {
+        let str = std::fs::read_to_string(path)?;
+        Self::from_bdf(&str)
+    }
+
+    pub fn line_height(&self) -> u32 {
+        self.ascent + self.descent
+    }
+
+    /// Glyph for `char`, falling back to uppercase then `?`
+    pub fn glyph(&self, char: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&char)
+            .or_else(|| self.glyphs.get(&char.to_ascii_uppercase()))
+            .or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// Width of `text` in keys, without the spacing after the last glyph
+    pub fn text_width(&self, text: &str) -> u32 {
+        let glyphs = text.chars().filter_map(|char| self.glyph(char));
+        let (advance, last) = glyphs.fold((0, None), |(advance, _), glyph| {
+            (advance + glyph.advance, Some(glyph))
+        });
+        match last {
+            Some(last) => (advance - last.advance)
+                .saturating_add_signed(last.x_offset)
+                .saturating_add(last.width),
+            None => 0,
+        }
+    }
+}
+
+impl<T: Clone> EffectMatrix<T> {
+    /// Draw `text` with the top left of the line at `x`, `y`, returning the pen position after
+    /// the text
+    pub fn draw_text(&mut self, font: &BitmapFont, text: &str, x: i32, y: i32, value: T) -> i32 {
+        let mut pen = x;
+        for glyph in text.chars().filter_map(|char| font.glyph(char)) {
+            for gy in 0..glyph.height {
+                for gx in 0..glyph.width {
+                    if glyph.get(gx, gy) {
+                        self.plot(
+                            pen + glyph.x_offset + gx as i32,
+                            y + glyph.top + gy as i32,
+                            value.clone(),
+                        );
+                    }
+                }
+            }
+            pen += glyph.advance as i32;
+        }
+        pen
+    }
+}
+
+/// Text scrolling from right to left
+#[derive(Debug, Clone)]
+pub struct Marquee {
+    pub text: String,
+    pub font: BitmapFont,
+    pub color: Color,
+    pub background: Color,
+    /// Keys per second, adjusted slightly so the animation loops on a whole frame
+    pub speed: f32,
+    /// Keys between repeats of the text, the width of the effect when `None`
+    pub gap: Option<u32>,
+    /// Row of the top of the text, vertically centered when `None`
+    pub y: Option<i32>,
+}
+
+impl Marquee {
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            font: BitmapFont::builtin_3x5(),
+            color: Color::new(1.0, 1.0, 1.0),
+            background: Color::default(),
+            speed: 8.0,
+            gap: None,
+            y: None,
+        }
+    }
+
+    /// Append the frames of the text scrolling once, which loops seamlessly
+    ///
+    /// Fails when the text and gap don't span any keys, or when `speed` is too slow for the
+    /// duration of one loop to be represented.
+    pub fn render(&self, effect: &mut Effect) -> Result<(), PolychromaticError> {
+        let width = effect.width();
+        let period = self
+            .font
+            .text_width(&self.text)
+            .checked_add(self.gap.unwrap_or(width))
+            .and_then(|period| i32::try_from(period).ok())
+            .filter(|period| *period > 0)
+            .ok_or(PolychromaticError::InvalidMarqueePeriod)?;
+        let y = self
+            .y
+            .unwrap_or_else(|| (effect.height() as i32 - self.font.line_height() as i32) / 2);
+        let duration = if self.speed > 0.0 {
+            Duration::try_from_secs_f32(period as f32 / self.speed)
+                .map_err(|_| PolychromaticError::InvalidSpeed(self.speed))?
+        } else {
+            Duration::ZERO
+        };
+
+        effect.render(duration, |time, frame| {
+            frame.fill(self.background);
+            let shift = (time.phase * period as f32).round() as i64;
+            let mut x = width as i64 - shift;
+            // Earlier repeats that are still on screen.
+            while x > 0 {
+                x -= period as i64;
+            }
+            while x < width as i64 {
+                frame.draw_text(&self.font, &self.text, x as i32, y, self.color);
+                x += period as i64;
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        BitmapFont, Color, Device, Effect, EffectMatrix, Keyboard, Marquee, PolychromaticError,
+    };
+
+    fn render(matrix: &EffectMatrix<u8>) -> String {
+        (0..matrix.height())
+            .map(|y| {
+                (0..matrix.width())
+                    .map(|x| {
+                        if matrix.get(x, y) == Some(&1) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_builtin() {
+        let font = BitmapFont::builtin_3x5();
+        for char in ' '..='~' {
+            assert!(font.glyphs.contains_key(&char.to_ascii_uppercase()));
+        }
+        assert_eq!(font.text_width("Hi"), 7);
+        let mut matrix = EffectMatrix::<u8>::new(8, 5);
+        assert_eq!(matrix.draw_text(&font, "Hi", 0, 0, 1), 8);
+        assert_eq!(
+            render(&matrix),
+            "#.#.###.\n#.#..#..\n###..#..\n#.#..#..\n#.#.###."
+        );
+    }
+
+    #[test]
+    fn test_builtin_5x6() {
+        let font = BitmapFont::builtin_5x6();
+        assert_eq!(font.line_height(), 6);
+        for char in ' '..='~' {
+            assert!(font.glyphs.contains_key(&char));
+        }
+        assert_eq!(font.text_width("gA"), 11);
+        let mut matrix = EffectMatrix::<u8>::new(11, 6);
+        assert_eq!(matrix.draw_text(&font, "gA", 0, 0, 1), 12);
+        assert_eq!(
+            render(&matrix),
+            ".......###.\n......#...#\n.####.#####\n#...#.#...#\n.####.#...#\n####......."
+        );
+    }
+
+    #[test]
+    fn test_bdf() {
+        let font = BitmapFont::from_bdf(
+            "STARTFONT 2.1
+FONTBOUNDINGBOX 3 4 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 3
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 2
+STARTCHAR period
+ENCODING 46
+DWIDTH 2 0
+BBX 1 1 0 0
+BITMAP
+80
+ENDCHAR
+STARTCHAR j
+ENCODING 106
+DWIDTH 3 0
+BBX 2 4 0 -1
+BITMAP
+40
+40
+40
+80
+ENDCHAR
+ENDFONT
+",
+        )
+        .unwrap();
+        assert_eq!(font.line_height(), 4);
+        assert_eq!(font.text_width(".j"), 4);
+        let mut matrix = EffectMatrix::<u8>::new(4, 4);
+        matrix.draw_text(&font, ".j", 0, 0, 1);
+        assert_eq!(render(&matrix), "...#\n...#\n#..#\n..#.");
+
+        assert!(BitmapFont::from_bdf("STARTCHAR a\nBITMAP\nENDCHAR\n").is_err());
+        // Rows wider than 64 bits.
+        let wide = BitmapFont::from_bdf(
+            "FONT_ASCENT 1\nSTARTCHAR a\nENCODING 97\nBBX 68 1 0 0\nBITMAP\n00000000000000001\nENDCHAR\n",
+        )
+        .unwrap();
+        let glyph = wide.glyph('a').unwrap();
+        assert!(glyph.get(67, 0) && !glyph.get(66, 0));
+
+        // Glyphs without a bitmap or with a BBX that changes after it.
+        let glyph = "FONT_ASCENT 1\nFONT_DESCENT 0\nSTARTCHAR a\nENCODING 97\nBBX 2 2 0 0\n";
+        assert!(BitmapFont::from_bdf(&format!("{glyph}ENDCHAR\n")).is_err());
+        assert!(
+            BitmapFont::from_bdf(&format!("{glyph}BITMAP\n80\n40\nBBX 3 3 0 0\nENDCHAR\n"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_marquee() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.set_fps(20).unwrap();
+        let mut marquee = Marquee::new("Hi");
+        marquee.speed = 10.0;
+        marquee.render(&mut effect).unwrap();
+
+        // 7 keys of text and a 15 key gap at 10 keys per second and 20 FPS.
+        assert_eq!(effect.frames().len(), 44);
+        let lit = |frame: usize| {
+            effect.frames()[frame]
+                .iter()
+                .filter(|(_, _, color)| **color == Color::new(1.0, 1.0, 1.0))
+                .count()
+        };
+        assert_eq!(lit(0), 0);
+        assert!(lit(20) > 0);
+
+        marquee.speed = f32::MIN_POSITIVE;
+        assert!(marquee.render(&mut effect).is_err());
+
+        let mut marquee = Marquee::new("");
+        marquee.gap = Some(0);
+        assert!(matches!(
+            marquee.render(&mut effect),
+            Err(PolychromaticError::InvalidMarqueePeriod)
+        ));
+        marquee.text = "Hi".to_owned();
+        marquee.gap = Some(u32::MAX);
+        assert!(matches!(
+            marquee.render(&mut effect),
+            Err(PolychromaticError::InvalidMarqueePeriod)
+        ));
+    }
+}
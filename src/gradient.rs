@@ -0,0 +1,303 @@
+//! Multi-stop color gradients
+
+use crate::{Color, Easing, EffectMatrix, Interpolate};
+
+/// Color space a [`Gradient`] interpolates in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    LinearRgb,
+    /// Takes the shortest path around the hue circle
+    Hsl,
+    /// Perceptually uniform, https://bottosson.github.io/posts/oklab/
+    Oklab,
+}
+
+impl ColorSpace {
+    pub fn interpolate(&self, from: &Color, to: &Color, t: f32) -> Color {
+        match self {
+            Self::Srgb => from.interpolate(to, t),
+            Self::LinearRgb => {
+                let (r0, g0, b0) = from.to_linear();
+                let (r1, g1, b1) = to.to_linear();
+                Color::from_linear(
+                    r0.interpolate(&r1, t),
+                    g0.interpolate(&g1, t),
+                    b0.interpolate(&b1, t),
+                )
+            }
+            Self::Hsl => {
+                let (h0, s0, l0) = from.to_hsl();
+                let (h1, s1, l1) = to.to_hsl();
+                // Grays have no hue, so keep the hue of the other color.
+                let h0 = if s0 == 0.0 { h1 } else { h0 };
+                let h1 = if s1 == 0.0 { h0 } else { h1 };
+                let dh = (h1 - h0 + 180.0).rem_euclid(360.0) - 180.0;
+                Color::from_hsl(h0 + dh * t, s0.interpolate(&s1, t), l0.interpolate(&l1, t))
+            }
+            Self::Oklab => {
+                let (l0, a0, b0) = from.to_oklab();
+                let (l1, a1, b1) = to.to_oklab();
+                Color::from_oklab(
+                    l0.interpolate(&l1, t),
+                    a0.interpolate(&a1, t),
+                    b0.interpolate(&b1, t),
+                )
+            }
+        }
+    }
+}
+
+/// What a [`Gradient`] does outside of `0.0..=1.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GradientMode {
+    /// Hold the first and last colors
+    #[default]
+    Clamp,
+    /// Start over, blending the last stop into the first stop
+    Repeat,
+    /// Go back and forth
+    Mirror,
+}
+
+/// How positions on an [`EffectMatrix`] map to a [`Gradient`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientShape {
+    /// Left to right
+    Horizontal,
+    /// Top to bottom
+    Vertical,
+    /// Along a direction in degrees, clockwise from left to right
+    Angle(f32),
+    /// Outwards from `center`, reaching `1.0` at `radius` keys away
+    Radial { center: (f32, f32), radius: f32 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GradientStop {
+    pub position: f32,
+    pub color: Color,
+    /// Easing of the segment starting at this stop
+    pub easing: Easing,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Gradient {
+    stops: Vec<GradientStop>,
+    pub space: ColorSpace,
+    pub mode: GradientMode,
+}
+
+impl Gradient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Colors spread evenly from `0.0` to `1.0`
+    pub fn evenly_spaced<I: IntoIterator<Item = Color>>(colors: I) -> Self {
+        let colors = colors.into_iter().collect::<Vec<_>>();
+        let last = (colors.len().max(2) - 1) as f32;
+        colors
+            .into_iter()
+            .enumerate()
+            .fold(Self::new(), |gradient, (i, color)| {
+                gradient.stop(i as f32 / last, color)
+            })
+    }
+
+    /// Colors spread evenly over `0.0..1.0` in [`GradientMode::Repeat`], so the last color blends
+    /// back into the first
+    pub fn looping<I: IntoIterator<Item = Color>>(colors: I) -> Self {
+        let colors = colors.into_iter().collect::<Vec<_>>();
+        let count = colors.len() as f32;
+        let mut gradient = colors
+            .into_iter()
+            .enumerate()
+            .fold(Self::new(), |gradient, (i, color)| {
+                gradient.stop(i as f32 / count, color)
+            });
+        gradient.mode = GradientMode::Repeat;
+        gradient
+    }
+
+    /// Add a stop, replacing any stop at the same position
+    pub fn insert(&mut self, position: f32, color: Color, easing: Easing) -> &mut Self {
+        let stop = GradientStop {
+            position,
+            color,
+            easing,
+        };
+        match self
+            .stops
+            .binary_search_by(|stop| stop.position.total_cmp(&position))
+        {
+            Ok(index) => self.stops[index] = stop,
+            Err(index) => self.stops.insert(index, stop),
+        }
+        self
+    }
+
+    /// Builder version of [`Gradient::insert`] with linear easing
+    pub fn stop(mut self, position: f32, color: Color) -> Self {
+        self.insert(position, color, Easing::Linear);
+        self
+    }
+
+    /// Builder version of [`Gradient::insert`]
+    pub fn stop_eased(mut self, position: f32, color: Color, easing: Easing) -> Self {
+        self.insert(position, color, easing);
+        self
+    }
+
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+
+    /// Stops can be edited in place, but must stay sorted by position
+    pub fn stops_mut(&mut self) -> &mut [GradientStop] {
+        &mut self.stops
+    }
+
+    /// Use `easing` for every segment
+    pub fn set_easing(&mut self, easing: Easing) -> &mut Self {
+        self.stops.iter_mut().for_each(|stop| stop.easing = easing);
+        self
+    }
+
+    /// Color at `position`, black if the gradient has no stops
+    pub fn sample(&self, position: f32) -> Color {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Color::default();
+        };
+
+        let position = match self.mode {
+            GradientMode::Clamp => position,
+            GradientMode::Repeat => position.rem_euclid(1.0),
+            GradientMode::Mirror => 1.0 - (position.rem_euclid(2.0) - 1.0).abs(),
+        };
+
+        let next = self.stops.partition_point(|stop| stop.position <= position);
+        let (from, to, to_position) = match (next.checked_sub(1), self.stops.get(next)) {
+            (Some(previous), Some(next)) => (&self.stops[previous], next, next.position),
+            (None, _) if self.mode == GradientMode::Repeat => (last, first, first.position),
+            (Some(_), None) if self.mode == GradientMode::Repeat => {
+                (last, first, first.position + 1.0)
+            }
+            (None, _) => return first.color,
+            (Some(_), None) => return last.color,
+        };
+
+        // Before the first stop when repeating, the wrapping segment started a cycle ago.
+        let from_position = if from.position > position {
+            from.position - 1.0
+        } else {
+            from.position
+        };
+        let t = if to_position > from_position {
+            (position - from_position) / (to_position - from_position)
+        } else {
+            0.0
+        };
+        self.space
+            .interpolate(&from.color, &to.color, from.easing.apply(t))
+    }
+
+    /// Fill `matrix` with the gradient laid out as `shape`, shifted along by `offset`
+    pub fn fill(&self, matrix: &mut EffectMatrix, shape: GradientShape, offset: f32) {
+        // Distance between the first and last key, so the last key gets the end stop.
+        let width = matrix.width().saturating_sub(1) as f32;
+        let height = matrix.height().saturating_sub(1) as f32;
+        let position: Box<dyn Fn(f32, f32) -> f32> = match shape {
+            GradientShape::Horizontal => Box::new(|x, _| x / width.max(1.0)),
+            GradientShape::Vertical => Box::new(|_, y| y / height.max(1.0)),
+            GradientShape::Angle(angle) => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let min = (0.0f32)
+                    .min(width * cos)
+                    .min(height * sin)
+                    .min(width * cos + height * sin);
+                let extent = (width * cos.abs() + height * sin.abs()).max(f32::EPSILON);
+                Box::new(move |x, y| (x * cos + y * sin - min) / extent)
+            }
+            GradientShape::Radial { center, radius } => Box::new(move |x, y| {
+                ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt() / radius
+            }),
+        };
+        matrix.iter_mut().for_each(|(x, y, color)| {
+            *color = self.sample(position(x as f32, y as f32) + offset);
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Color, ColorSpace, EffectMatrix, Gradient, GradientMode, GradientShape};
+
+    fn assert_close(a: Color, b: Color) {
+        assert!(a.distance(&b) < 1e-3, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_sample() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let mut gradient = Gradient::evenly_spaced([red, blue]);
+        assert_close(gradient.sample(-1.0), red);
+        assert_close(gradient.sample(0.5), Color::new(0.5, 0.0, 0.5));
+        assert_close(gradient.sample(2.0), blue);
+
+        gradient.mode = GradientMode::Mirror;
+        assert_close(gradient.sample(1.5), Color::new(0.5, 0.0, 0.5));
+        assert_close(gradient.sample(2.0), red);
+
+        gradient.space = ColorSpace::Hsl;
+        // Red to blue is shorter going backwards through magenta.
+        assert_close(gradient.sample(0.5), Color::new(1.0, 0.0, 1.0));
+
+        let gradient = Gradient::looping([red, blue]);
+        assert_close(gradient.sample(0.25), Color::new(0.5, 0.0, 0.5));
+        assert_close(gradient.sample(0.75), Color::new(0.5, 0.0, 0.5));
+        assert_close(gradient.sample(1.0), red);
+    }
+
+    #[test]
+    fn test_color_spaces() {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        for space in [
+            ColorSpace::Srgb,
+            ColorSpace::LinearRgb,
+            ColorSpace::Hsl,
+            ColorSpace::Oklab,
+        ] {
+            assert_close(space.interpolate(&black, &white, 0.0), black);
+            assert_close(space.interpolate(&black, &white, 1.0), white);
+        }
+        let gray = ColorSpace::LinearRgb.interpolate(&black, &white, 0.5);
+        assert!(gray.r > 0.7);
+    }
+
+    #[test]
+    fn test_fill() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let gradient = Gradient::evenly_spaced([red, blue]);
+        let mut matrix = EffectMatrix::new(5, 3);
+        for shape in [
+            GradientShape::Horizontal,
+            GradientShape::Vertical,
+            GradientShape::Angle(45.0),
+        ] {
+            gradient.fill(&mut matrix, shape, 0.0);
+            assert_close(*matrix.get(0, 0).unwrap(), red);
+            assert_close(*matrix.get(4, 2).unwrap(), blue);
+        }
+        gradient.fill(&mut matrix, GradientShape::Horizontal, 0.0);
+        assert_close(*matrix.get(2, 1).unwrap(), Color::new(0.5, 0.0, 0.5));
+
+        let mut matrix = EffectMatrix::new(1, 1);
+        gradient.fill(&mut matrix, GradientShape::Angle(30.0), 0.0);
+        assert_close(*matrix.get(0, 0).unwrap(), red);
+    }
+}
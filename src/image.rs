@@ -0,0 +1,548 @@
+//! Importing PNG, GIF and PPM images as effect frames
+
+use std::{io::Cursor, path::Path, time::Duration};
+
+use crate::{Color, Effect, EffectMatrix, PolychromaticError, Rgba};
+
+/// GIF frames without a delay are shown for 100ms, like web browsers do
+const DEFAULT_GIF_DELAY: Duration = Duration::from_millis(100);
+
+/// How image pixels are sampled when resizing to the effect matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFilter {
+    /// Pixel under the center of each key, keeps pixel art crisp
+    Nearest,
+    /// Average of all pixels covered by each key
+    #[default]
+    Box,
+    /// Interpolate the four pixels nearest the center of each key
+    Bilinear,
+}
+
+/// How the image aspect ratio is handled when it doesn't match the effect matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFit {
+    /// Scale to fit inside the matrix, uncovered keys are transparent
+    Fit,
+    /// Scale to cover the whole matrix, cropping the image
+    Fill,
+    /// Scale each axis independently to the matrix size
+    #[default]
+    Stretch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ImageOptions {
+    pub filter: ImageFilter,
+    pub fit: ImageFit,
+    /// Shown behind transparent pixels
+    pub background: Color,
+}
+
+/// Decoded image, animated GIFs have a frame for each image
+#[derive(Debug, Clone)]
+pub struct ImageFrame {
+    pub image: EffectMatrix<Rgba>,
+    /// How long the frame is shown, zero for still images
+    pub delay: Duration,
+}
+
+impl ImageFrame {
+    /// Decode a PNG, GIF or PPM/PGM image, detected from its contents
+    pub fn decode(bytes: &[u8]) -> Result<Vec<ImageFrame>, PolychromaticError> {
+        match bytes {
+            [0x89, b'P', b'N', b'G', ..] => decode_png(bytes),
+            [b'G', b'I', b'F', b'8', ..] => decode_gif(bytes),
+            [b'P', b'2' | b'3' | b'5' | b'6', ..] => decode_pnm(bytes),
+            _ => Err(PolychromaticError::CannotParseImage(
+                "Unsupported format, expected PNG, GIF or PPM",
+            )),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<ImageFrame>, PolychromaticError> {
+        Self::decode(&std::fs::read(path)?)
+    }
+}
+
+fn from_rgba8(width: u32, height: u32, rgba: &[u8]) -> EffectMatrix<Rgba> {
+    let mut image = EffectMatrix::new(width, height);
+    for (pixel, rgba) in image.values_mut().iter_mut().zip(rgba.chunks_exact(4)) {
+        *pixel = Rgba::from_quantized(rgba[0], rgba[1], rgba[2], rgba[3]);
+    }
+    image
+}
+
+fn decode_png(bytes: &[u8]) -> Result<Vec<ImageFrame>, PolychromaticError> {
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let rgba = buffer[..info.buffer_size()]
+        .chunks_exact(info.color_type.samples())
+        .flat_map(|pixel| match *pixel {
+            [l] => [l, l, l, 255],
+            [l, a] => [l, l, l, a],
+            [r, g, b] => [r, g, b, 255],
+            [r, g, b, a] => [r, g, b, a],
+            _ => unreachable!("PNG pixels have 1 to 4 samples"),
+        })
+        .collect::<Vec<_>>();
+    Ok(vec![ImageFrame {
+        image: from_rgba8(info.width, info.height, &rgba),
+        delay: Duration::ZERO,
+    }])
+}
+
+fn decode_gif(bytes: &[u8]) -> Result<Vec<ImageFrame>, PolychromaticError> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(Cursor::new(bytes))?;
+    let (width, height) = (decoder.width() as usize, decoder.height() as usize);
+
+    // Frames only cover part of the image and are drawn over the previous ones.
+    let mut canvas = vec![0; width * height * 4];
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame()? {
+        let previous = (frame.dispose == gif::DisposalMethod::Previous).then(|| canvas.clone());
+        // Frames may reach past the logical screen, or lie outside it entirely.
+        let rows = (frame.top as usize).min(height)
+            ..(frame.top as usize + frame.height as usize).min(height);
+        let cols = (frame.left as usize).min(width)
+            ..(frame.left as usize + frame.width as usize).min(width);
+        for (y, row) in rows
+            .clone()
+            .zip(frame.buffer.chunks_exact(frame.width as usize * 4))
+        {
+            for (x, pixel) in cols.clone().zip(row.chunks_exact(4)) {
+                if pixel[3] != 0 {
+                    let index = (x + y * width) * 4;
+                    canvas[index..index + 4].copy_from_slice(pixel);
+                }
+            }
+        }
+
+        let delay = match frame.delay {
+            0 => DEFAULT_GIF_DELAY,
+            delay => Duration::from_millis(delay as u64 * 10),
+        };
+        frames.push(ImageFrame {
+            image: from_rgba8(width as u32, height as u32, &canvas),
+            delay,
+        });
+
+        match (frame.dispose, previous) {
+            (_, Some(previous)) => canvas = previous,
+            (gif::DisposalMethod::Background, _) => {
+                for y in rows {
+                    let start = (cols.start + y * width) * 4;
+                    let end = (cols.end + y * width) * 4;
+                    canvas[start..end].fill(0);
+                }
+            }
+            _ => {}
+        }
+    }
+    if frames.is_empty() {
+        return Err(PolychromaticError::CannotParseImage("GIF has no frames"));
+    }
+    if let [frame] = frames.as_mut_slice() {
+        frame.delay = Duration::ZERO;
+    }
+    Ok(frames)
+}
+
+/// Binary and plain PPM (`P6`, `P3`) and PGM (`P5`, `P2`)
+fn decode_pnm(bytes: &[u8]) -> Result<Vec<ImageFrame>, PolychromaticError> {
+    let binary = matches!(bytes[1], b'5' | b'6');
+    let channels = if matches!(bytes[1], b'3' | b'6') {
+        3
+    } else {
+        1
+    };
+
+    // Header fields are separated by whitespace and may be followed by `#` comments.
+    let mut position = 2;
+    let mut header = [0; 3];
+    for field in &mut header {
+        loop {
+            match bytes.get(position) {
+                Some(b'#') => {
+                    while bytes.get(position).is_some_and(|byte| *byte != b'\n') {
+                        position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => position += 1,
+                _ => break,
+            }
+        }
+        let start = position;
+        while bytes.get(position).is_some_and(u8::is_ascii_digit) {
+            position += 1;
+        }
+        *field = std::str::from_utf8(&bytes[start..position])
+            .ok()
+            .and_then(|field| field.parse::<u32>().ok())
+            .ok_or(PolychromaticError::CannotParseImage("Invalid PPM header"))?;
+    }
+    let [width, height, max] = header;
+    if max == 0 || max > u16::MAX as u32 {
+        return Err(PolychromaticError::CannotParseImage(
+            "Invalid PPM maximum value",
+        ));
+    }
+    let count = width as usize * height as usize * channels;
+
+    let samples: Vec<u32> = if binary {
+        // A single whitespace byte separates the header from the samples.
+        let data = bytes.get(position + 1..).unwrap_or_default();
+        if max < 256 {
+            data.iter().map(|sample| *sample as u32).collect()
+        } else {
+            data.chunks_exact(2)
+                .map(|sample| u16::from_be_bytes([sample[0], sample[1]]) as u32)
+                .collect()
+        }
+    } else {
+        std::str::from_utf8(&bytes[position..])
+            .map_err(|_| PolychromaticError::CannotParseImage("Invalid PPM samples"))?
+            .lines()
+            .flat_map(|line| {
+                line.split('#')
+                    .next()
+                    .unwrap_or_default()
+                    .split_whitespace()
+            })
+            .map(|sample| sample.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| PolychromaticError::CannotParseImage("Invalid PPM samples"))?
+    };
+    if samples.len() < count {
+        return Err(PolychromaticError::CannotParseImage(
+            "PPM data is too short",
+        ));
+    }
+
+    let mut image = EffectMatrix::new(width, height);
+    for (pixel, samples) in image
+        .values_mut()
+        .iter_mut()
+        .zip(samples[..count].chunks_exact(channels))
+    {
+        let channel = |index: usize| samples[index.min(channels - 1)] as f32 / max as f32;
+        *pixel = Rgba::new(channel(0), channel(1), channel(2), 1.0);
+    }
+    Ok(vec![ImageFrame {
+        image,
+        delay: Duration::ZERO,
+    }])
+}
+
+/// Premultiplied alpha, so transparent pixels don't darken their neighbours when averaged
+fn premultiply(color: &Rgba) -> [f32; 4] {
+    [
+        color.r * color.a,
+        color.g * color.a,
+        color.b * color.a,
+        color.a,
+    ]
+}
+
+fn unpremultiply([r, g, b, a]: [f32; 4]) -> Rgba {
+    if a <= 0.0 {
+        return Rgba::default();
+    }
+    Rgba::new(r / a, g / a, b / a, a.min(1.0))
+}
+
+fn add_weighted(sum: &mut [f32; 4], color: [f32; 4], weight: f32) {
+    sum.iter_mut()
+        .zip(color)
+        .for_each(|(sum, value)| *sum += value * weight);
+}
+
+impl EffectMatrix<Rgba> {
+    /// Resample the image to `width` by `height` keys, keys outside the image are transparent
+    pub fn resize(&self, width: u32, height: u32, filter: ImageFilter, fit: ImageFit) -> Self {
+        let (source_width, source_height) = (self.width() as f32, self.height() as f32);
+        let mut scale_x = width as f32 / source_width;
+        let mut scale_y = height as f32 / source_height;
+        match fit {
+            ImageFit::Fit => {
+                scale_x = scale_x.min(scale_y);
+                scale_y = scale_x;
+            }
+            ImageFit::Fill => {
+                scale_x = scale_x.max(scale_y);
+                scale_y = scale_x;
+            }
+            ImageFit::Stretch => {}
+        }
+        let offset_x = (width as f32 - source_width * scale_x) / 2.0;
+        let offset_y = (height as f32 - source_height * scale_y) / 2.0;
+        // Image coordinates of a position in the resized matrix.
+        let to_image = |x: f32, y: f32| ((x - offset_x) / scale_x, (y - offset_y) / scale_y);
+        let pixel = |x: i64, y: i64| {
+            let x = x.clamp(0, self.width() as i64 - 1) as u32;
+            let y = y.clamp(0, self.height() as i64 - 1) as u32;
+            premultiply(self.get(x, y).unwrap())
+        };
+
+        let mut resized = EffectMatrix::new(width, height);
+        if self.width() == 0 || self.height() == 0 {
+            return resized;
+        }
+        for (x, y, color) in resized.iter_mut() {
+            let (u, v) = to_image(x as f32 + 0.5, y as f32 + 0.5);
+            let inside = (0.0..source_width).contains(&u) && (0.0..source_height).contains(&v);
+            *color = match filter {
+                ImageFilter::Nearest if inside => unpremultiply(pixel(u as i64, v as i64)),
+                ImageFilter::Bilinear if inside => {
+                    let (u, v) = (u - 0.5, v - 0.5);
+                    let (x0, y0) = (u.floor() as i64, v.floor() as i64);
+                    let (fx, fy) = (u - u.floor(), v - v.floor());
+                    let mut sum = [0.0; 4];
+                    add_weighted(&mut sum, pixel(x0, y0), (1.0 - fx) * (1.0 - fy));
+                    add_weighted(&mut sum, pixel(x0 + 1, y0), fx * (1.0 - fy));
+                    add_weighted(&mut sum, pixel(x0, y0 + 1), (1.0 - fx) * fy);
+                    add_weighted(&mut sum, pixel(x0 + 1, y0 + 1), fx * fy);
+                    unpremultiply(sum)
+                }
+                ImageFilter::Box => {
+                    let (u0, v0) = to_image(x as f32, y as f32);
+                    let (u1, v1) = to_image(x as f32 + 1.0, y as f32 + 1.0);
+                    let area = (u1 - u0) * (v1 - v0);
+                    let mut sum = [0.0; 4];
+                    let (cu0, cu1) = (u0.max(0.0), u1.min(source_width));
+                    let (cv0, cv1) = (v0.max(0.0), v1.min(source_height));
+                    let mut py = cv0.floor();
+                    while py < cv1 {
+                        let weight_y = (py + 1.0).min(cv1) - py.max(cv0);
+                        let mut px = cu0.floor();
+                        while px < cu1 {
+                            let weight_x = (px + 1.0).min(cu1) - px.max(cu0);
+                            add_weighted(
+                                &mut sum,
+                                pixel(px as i64, py as i64),
+                                weight_x * weight_y / area,
+                            );
+                            px += 1.0;
+                        }
+                        py += 1.0;
+                    }
+                    unpremultiply(sum)
+                }
+                _ => Rgba::default(),
+            };
+        }
+        resized
+    }
+}
+
+impl EffectMatrix {
+    /// Resize `image` to `width` by `height` keys and draw it over the background
+    pub fn from_image(
+        image: &EffectMatrix<Rgba>,
+        width: u32,
+        height: u32,
+        options: ImageOptions,
+    ) -> Self {
+        let resized = image.resize(width, height, options.filter, options.fit);
+        let mut matrix = EffectMatrix::new(width, height);
+        for (color, pixel) in matrix.values_mut().iter_mut().zip(resized.values()) {
+            *color = pixel.over_color(&options.background);
+        }
+        matrix
+    }
+
+    /// Load the first frame of an image file, see [`EffectMatrix::from_image`]
+    pub fn load_image<P: AsRef<Path>>(
+        path: P,
+        width: u32,
+        height: u32,
+        options: ImageOptions,
+    ) -> Result<Self, PolychromaticError> {
+        let frames = ImageFrame::load(path)?;
+        Ok(Self::from_image(&frames[0].image, width, height, options))
+    }
+}
+
+impl Effect {
+    /// Append the frames of an image, animations are resampled to the effect's FPS by showing
+    /// the image frame at the time of each effect frame
+    pub fn import_frames(&mut self, frames: &[ImageFrame], options: ImageOptions) {
+        let matrices = frames
+            .iter()
+            .map(|frame| {
+                EffectMatrix::from_image(&frame.image, self.width(), self.height(), options)
+            })
+            .collect::<Vec<_>>();
+        let duration = frames.iter().map(|frame| frame.delay).sum::<Duration>();
+        let ends = frames
+            .iter()
+            .scan(Duration::ZERO, |end, frame| {
+                *end += frame.delay;
+                Some(end.as_secs_f32())
+            })
+            .collect::<Vec<_>>();
+        self.render(duration, |time, frame| {
+            let index = ends
+                .iter()
+                .position(|end| time.seconds < *end)
+                .unwrap_or(0)
+                .min(matrices.len().saturating_sub(1));
+            if let Some(matrix) = matrices.get(index) {
+                frame.values_mut().clone_from_slice(matrix.values());
+            }
+        });
+    }
+
+    /// Append the frames of a PNG, GIF or PPM file, see [`Effect::import_frames`]
+    pub fn import_image<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: ImageOptions,
+    ) -> Result<(), PolychromaticError> {
+        let frames = ImageFrame::load(path)?;
+        self.import_frames(&frames, options);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        Color, Device, Effect, EffectMatrix, ImageFilter, ImageFit, ImageFrame, ImageOptions,
+        Keyboard, Rgba,
+    };
+
+    fn gif(frames: &[([u8; 4], u16)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = gif::Encoder::new(&mut bytes, 2, 2, &[]).unwrap();
+        for (color, delay) in frames {
+            let mut pixels = color.repeat(4);
+            let mut frame = gif::Frame::from_rgba(2, 2, &mut pixels);
+            frame.delay = *delay;
+            encoder.write_frame(&frame).unwrap();
+        }
+        drop(encoder);
+        bytes
+    }
+
+    #[test]
+    fn test_decode() {
+        let ppm = ImageFrame::decode(b"P3\n# comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+        assert_eq!(ppm[0].image.values()[1], Rgba::new(0.0, 0.0, 1.0, 1.0));
+        let pgm = ImageFrame::decode(b"P5 1 1 255\n\x80").unwrap();
+        assert_eq!(pgm[0].image.values()[0].to_hex(), "#808080FF");
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 1, 2);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[255, 255, 0, 0]).unwrap();
+        drop(writer);
+        let png = ImageFrame::decode(&png).unwrap();
+        assert_eq!(
+            png[0].image.values(),
+            [Rgba::new(1.0, 1.0, 1.0, 1.0), Rgba::default()]
+        );
+
+        let gif =
+            ImageFrame::decode(&gif(&[([255, 0, 0, 255], 5), ([0, 255, 0, 255], 0)])).unwrap();
+        assert_eq!(gif.len(), 2);
+        assert_eq!(gif[0].delay, Duration::from_millis(50));
+        assert_eq!(gif[1].delay, Duration::from_millis(100));
+        assert_eq!(gif[1].image.values()[3].to_hex(), "#00FF00FF");
+
+        let mut bytes = Vec::new();
+        let mut encoder = gif::Encoder::new(&mut bytes, 2, 2, &[]).unwrap();
+        let mut pixels = [255, 0, 0, 255].repeat(4);
+        let mut frame = gif::Frame::from_rgba(2, 2, &mut pixels);
+        (frame.left, frame.top) = (5, 1);
+        frame.dispose = gif::DisposalMethod::Background;
+        encoder.write_frame(&frame).unwrap();
+        drop(encoder);
+        let gif = ImageFrame::decode(&bytes).unwrap();
+        assert_eq!(gif[0].image.values(), [Rgba::default(); 4]);
+
+        assert!(ImageFrame::decode(b"BM").is_err());
+    }
+
+    #[test]
+    fn test_resize() {
+        // Left half white, right half transparent.
+        let mut image = EffectMatrix::<Rgba>::new(4, 2);
+        for (x, _, pixel) in image.iter_mut() {
+            if x < 2 {
+                *pixel = Rgba::new(1.0, 1.0, 1.0, 1.0);
+            }
+        }
+        let resize = |width, height, filter, fit| {
+            let resized: EffectMatrix<Rgba> = image.resize(width, height, filter, fit);
+            resized
+                .values()
+                .iter()
+                .map(|pixel| pixel.a)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            resize(2, 1, ImageFilter::Box, ImageFit::Stretch),
+            [1.0, 0.0]
+        );
+        assert_eq!(resize(1, 1, ImageFilter::Box, ImageFit::Stretch), [0.5]);
+        assert_eq!(
+            resize(4, 1, ImageFilter::Nearest, ImageFit::Fill),
+            [1.0, 1.0, 0.0, 0.0]
+        );
+        // Fitting a 2:1 image in a square leaves transparent rows above and below.
+        assert_eq!(
+            resize(4, 4, ImageFilter::Nearest, ImageFit::Fit),
+            [
+                0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0
+            ]
+        );
+        assert_eq!(
+            resize(8, 1, ImageFilter::Bilinear, ImageFit::Stretch)[3..5],
+            [0.75, 0.25]
+        );
+
+        let matrix = EffectMatrix::from_image(
+            &image,
+            1,
+            1,
+            ImageOptions {
+                background: Color::new(0.0, 0.0, 1.0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(matrix.values()[0], Color::new(0.5, 0.5, 1.0));
+    }
+
+    #[test]
+    fn test_import() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.set_fps(20).unwrap();
+        let frames =
+            ImageFrame::decode(&gif(&[([255, 0, 0, 255], 20), ([0, 0, 255, 255], 5)])).unwrap();
+        effect.import_frames(&frames, ImageOptions::default());
+
+        // 200ms of red and 50ms of blue at 50ms per frame.
+        let colors = effect
+            .frames()
+            .iter()
+            .map(|frame| frame.values()[0].to_hex())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            colors,
+            ["#FF0000", "#FF0000", "#FF0000", "#FF0000", "#0000FF"]
+        );
+    }
+}
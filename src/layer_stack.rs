@@ -0,0 +1,218 @@
+//! Composites named layers into effect frames
+
+use std::time::Duration;
+
+use crate::{BlendMode, Color, Effect, EffectMatrix, FrameTime, Rgba};
+
+#[derive(Debug, Clone)]
+pub struct StackLayer {
+    pub name: String,
+    /// Starts out fully transparent
+    pub matrix: EffectMatrix<Rgba>,
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+    /// How much of the layer is shown on each key, from `0.0` to `1.0`
+    pub mask: Option<EffectMatrix<f32>>,
+    pub visible: bool,
+}
+
+impl StackLayer {
+    pub fn new<S: Into<String>>(name: S, width: u32, height: u32) -> Self {
+        Self {
+            name: name.into(),
+            matrix: EffectMatrix::new(width, height),
+            opacity: 1.0,
+            blend_mode: BlendMode::Normal,
+            mask: None,
+            visible: true,
+        }
+    }
+
+    /// Make every key transparent
+    pub fn clear(&mut self) {
+        self.matrix.values_mut().fill(Rgba::default());
+    }
+
+    /// Blend the layer on top of `frame`
+    pub fn composite(&self, frame: &mut EffectMatrix) {
+        if !self.visible {
+            return;
+        }
+        frame.iter_mut().for_each(|(x, y, backdrop)| {
+            let Some(source) = self.matrix.get(x, y) else {
+                return;
+            };
+            let mask = self
+                .mask
+                .as_ref()
+                .map_or(1.0, |mask| mask.get(x, y).copied().unwrap_or(0.0));
+            let alpha = source.a * self.opacity * mask;
+            if alpha > 0.0 {
+                *backdrop = self
+                    .blend_mode
+                    .blend_with_opacity(backdrop, &source.color(), alpha);
+            }
+        });
+    }
+}
+
+/// Layers composited bottom to top
+#[derive(Debug, Clone)]
+pub struct LayerStack {
+    width: u32,
+    height: u32,
+    layers: Vec<StackLayer>,
+}
+
+impl LayerStack {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Stack sized to the frames of `effect`
+    pub fn for_effect(effect: &Effect) -> Self {
+        Self::new(effect.width(), effect.height())
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn layers(&self) -> &[StackLayer] {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut [StackLayer] {
+        &mut self.layers
+    }
+
+    /// Add a transparent layer on top
+    pub fn push<S: Into<String>>(&mut self, name: S) -> &mut StackLayer {
+        self.layers
+            .push(StackLayer::new(name, self.width, self.height));
+        self.layers.last_mut().unwrap()
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&StackLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut StackLayer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<StackLayer> {
+        let index = self.layers.iter().position(|layer| layer.name == name)?;
+        Some(self.layers.remove(index))
+    }
+
+    /// Blend every visible layer on top of `frame`
+    pub fn composite(&self, frame: &mut EffectMatrix) {
+        self.layers.iter().for_each(|layer| layer.composite(frame));
+    }
+
+    /// Composite onto a black frame
+    pub fn flatten(&self) -> EffectMatrix {
+        let mut frame = EffectMatrix::<Color>::new(self.width, self.height);
+        self.composite(&mut frame);
+        frame
+    }
+}
+
+impl Effect {
+    /// Append frames lasting `duration`, calling `animate` to update the layers of `stack`
+    /// before compositing them into each new frame
+    pub fn render_layers<F: FnMut(FrameTime, &mut LayerStack)>(
+        &mut self,
+        duration: Duration,
+        stack: &mut LayerStack,
+        mut animate: F,
+    ) {
+        self.render(duration, |time, frame| {
+            animate(time, stack);
+            stack.composite(frame);
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{BlendMode, Color, Device, Effect, EffectMatrix, Keyboard, LayerStack};
+
+    #[test]
+    fn test_layer_stack() {
+        let mut stack = LayerStack::new(4, 1);
+        stack
+            .push("background")
+            .matrix
+            .values_mut()
+            .fill(Color::new(0.0, 0.0, 1.0).into());
+        let highlight = stack.push("highlight");
+        highlight
+            .matrix
+            .set(Color::new(1.0, 0.0, 0.0).with_alpha(0.5), 1, 0);
+        highlight.matrix.set(Color::new(1.0, 0.0, 0.0).into(), 2, 0);
+        highlight.matrix.set(Color::new(1.0, 0.0, 0.0).into(), 3, 0);
+        let mut mask = EffectMatrix::new(4, 1);
+        mask.values_mut().copy_from_slice(&[1.0, 1.0, 1.0, 0.0]);
+        highlight.mask = Some(mask);
+
+        let frame = stack.flatten();
+        assert_eq!(
+            frame.values(),
+            &[
+                Color::new(0.0, 0.0, 1.0),
+                Color::new(0.5, 0.0, 0.5),
+                Color::new(1.0, 0.0, 0.0),
+                Color::new(0.0, 0.0, 1.0),
+            ]
+        );
+
+        let highlight = stack.layer_mut("highlight").unwrap();
+        highlight.blend_mode = BlendMode::Add;
+        highlight.opacity = 0.5;
+        assert_eq!(stack.flatten().get(2, 0), Some(&Color::new(0.5, 0.0, 1.0)));
+
+        stack.layer_mut("highlight").unwrap().visible = false;
+        assert_eq!(stack.flatten().get(2, 0), Some(&Color::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_render_layers() {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.set_fps(10).unwrap();
+        let mut stack = LayerStack::for_effect(&effect);
+        stack.push("dot");
+        effect.render_layers(Duration::from_secs(1), &mut stack, |time, stack| {
+            let dot = stack.layer_mut("dot").unwrap();
+            dot.clear();
+            dot.matrix
+                .set(Color::new(1.0, 1.0, 1.0).into(), time.index as u32, 0);
+        });
+        assert_eq!(effect.frames().len(), 10);
+        for (i, frame) in effect.frames().iter().enumerate() {
+            assert_eq!(frame.get(i as u32, 0), Some(&Color::new(1.0, 1.0, 1.0)));
+            assert_eq!(
+                frame
+                    .iter()
+                    .filter(|(_, _, color)| !color.is_black())
+                    .count(),
+                1
+            );
+        }
+    }
+}
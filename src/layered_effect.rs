@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    PolychromaticError, defs,
+    device::{Device, KeyboardLayout},
+};
+
+pub use defs::{Layer, LayerKind, WaveDirection};
+
+/// Effect made of stacked layers that polychromatic animates itself
+#[derive(Debug)]
+pub struct LayeredEffect {
+    pub name: String,
+    pub author: String,
+    pub icon: PathBuf,
+    pub summary: String,
+    /// Layout of the graphic shown in the polychromatic editor
+    pub layout: KeyboardLayout,
+    /// Write indented JSON
+    pub pretty: bool,
+    device: Device,
+    width: u32,
+    height: u32,
+    layers: Vec<Layer>,
+}
+
+impl LayeredEffect {
+    pub fn new<P: AsRef<Path>>(device: Device, icon: P) -> Result<Self, PolychromaticError> {
+        let (width, height) = device
+            .matrix()
+            .ok_or(PolychromaticError::DeviceUnsupportedEffects(device))?;
+        Ok(Self {
+            name: "Unnamed".to_owned(),
+            author: String::new(),
+            icon: icon.as_ref().to_path_buf(),
+            summary: String::new(),
+            layout: KeyboardLayout::default(),
+            pretty: false,
+            device,
+            width,
+            height,
+            layers: Vec::new(),
+        })
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut Vec<Layer> {
+        &mut self.layers
+    }
+
+    /// Add a layer on top of the existing layers
+    pub fn add_layer<S: Into<String>>(&mut self, name: S, kind: LayerKind) -> &mut Self {
+        self.layers.push(Layer {
+            name: name.into(),
+            kind,
+        });
+        self
+    }
+
+    pub fn to_effect_json(&self) -> Result<String, PolychromaticError> {
+        Ok(defs::to_json(
+            &defs::LayeredEffect {
+                name: self.name.clone(),
+                author: self.author.clone(),
+                icon: self.icon.clone().canonicalize()?,
+                summary: self.summary.clone(),
+                r#type: 1,
+                map_device: self.device.to_string(),
+                map_device_icon: self.device.icon().to_owned(),
+                map_graphic: self.device.map_graphic(self.layout).unwrap_or_default(),
+                map_cols: self.width,
+                map_rows: self.height,
+                save_format: 8,
+                revision: 1,
+                layers: self.layers.clone(),
+            },
+            self.pretty,
+        )?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PolychromaticError> {
+        let str = self.to_effect_json()?;
+        std::fs::write(path, &str)?;
+        Ok(())
+    }
+
+    /// Parse a polychromatic layered effect
+    pub fn from_effect_json(str: &str) -> Result<Self, PolychromaticError> {
+        let def: defs::LayeredEffect = serde_json::from_str(str)?;
+
+        if def.r#type != 1 {
+            return Err(PolychromaticError::UnsupportedEffectType(def.r#type, 1));
+        }
+
+        let device = Device::from_map(&def.map_device, def.map_cols, def.map_rows)?;
+
+        let mut effect = Self::new(device, &def.icon)?;
+        effect.name = def.name;
+        effect.author = def.author;
+        effect.summary = def.summary;
+        if let Some(layout) = KeyboardLayout::from_map_graphic(&def.map_graphic) {
+            effect.layout = layout;
+        }
+
+        for (index, layer) in def.layers.iter().enumerate() {
+            if let LayerKind::Keys { keys } = &layer.kind
+                && let Some((x, y, _)) = keys
+                    .iter()
+                    .find(|(x, y, _)| *x >= effect.width || *y >= effect.height)
+            {
+                return Err(PolychromaticError::MalformedLayer {
+                    layer: index,
+                    reason: format!("Position {x}, {y} out of bounds"),
+                });
+            }
+        }
+        effect.layers = def.layers;
+
+        Ok(effect)
+    }
+
+    /// Load a polychromatic layered effect file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PolychromaticError> {
+        let str = std::fs::read_to_string(path)?;
+        Self::from_effect_json(&str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Color, Device, Keyboard, LayerKind, LayeredEffect, PolychromaticError, WaveDirection,
+    };
+
+    #[test]
+    fn test_layered_effect() {
+        let mut effect = LayeredEffect::new(
+            Device::Keyboard(Keyboard::RazerBlackWidowV3),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect
+            .add_layer(
+                "Background",
+                LayerKind::Wave {
+                    direction: WaveDirection::Left,
+                    speed: 3,
+                },
+            )
+            .add_layer(
+                "WASD",
+                LayerKind::Keys {
+                    keys: vec![(2, 2, Color::from_quantized(255, 0, 0))],
+                },
+            );
+
+        let loaded = LayeredEffect::from_effect_json(&effect.to_effect_json().unwrap()).unwrap();
+        assert_eq!(loaded.layers(), effect.layers());
+
+        effect.add_layer(
+            "Out of bounds",
+            LayerKind::Keys {
+                keys: vec![(22, 0, Color::default())],
+            },
+        );
+        assert!(matches!(
+            LayeredEffect::from_effect_json(&effect.to_effect_json().unwrap()),
+            Err(PolychromaticError::MalformedLayer { layer: 2, .. })
+        ));
+    }
+}
@@ -3,15 +3,49 @@ compile_error!("polychromatic-rs is only for Linux");
 
 use thiserror::Error;
 
+pub mod antialias;
+pub mod blend;
 mod color;
 mod defs;
 pub mod device;
+pub mod device_map;
+mod draw;
+pub mod easing;
 pub mod effect;
+pub mod effect_library;
+pub mod effect_writer;
+pub mod font;
+pub mod gradient;
+pub mod image;
+pub mod layer_stack;
+pub mod layered_effect;
+pub mod physical_layout;
+pub mod player;
+pub mod preview;
 mod proc_bus_input_devices;
+pub mod scripted_effect;
+mod svg;
+pub mod timeline;
 
+pub use antialias::*;
+pub use blend::*;
 pub use color::*;
 pub use device::*;
+pub use device_map::*;
+pub use easing::*;
 pub use effect::*;
+pub use effect_library::*;
+pub use effect_writer::*;
+pub use font::*;
+pub use gradient::*;
+pub use image::*;
+pub use layer_stack::*;
+pub use layered_effect::*;
+pub use physical_layout::*;
+pub use player::*;
+pub use preview::*;
+pub use scripted_effect::*;
+pub use timeline::*;
 
 pub(crate) const FPS_RANGE: std::ops::RangeInclusive<u32> = 1..=80;
 
@@ -29,4 +63,52 @@ pub enum PolychromaticError {
     DeviceUnsupportedEffects(Device),
     #[error("Invalid FPS value {0} must be in range 1..=80")]
     InvalidFPS(u32),
+    #[error("Unsupported effect type {0}, expected {1}")]
+    UnsupportedEffectType(i64, i64),
+    #[error("Unknown device {0:?}")]
+    UnknownDevice(String),
+    #[error("Effect matrix {0}x{1} doesn't match {2:?} matrix {3}x{4}")]
+    MatrixMismatch(u32, u32, Device, u32, u32),
+    #[error("Malformed effect frame {frame}: {reason}")]
+    MalformedFrame { frame: usize, reason: String },
+    #[error("Malformed effect layer {layer}: {reason}")]
+    MalformedLayer { layer: usize, reason: String },
+    #[error("Frame size {0}x{1} doesn't match effect size {2}x{3}")]
+    FrameSizeMismatch(u32, u32, u32, u32),
+    #[error("Invalid parameter {0:?}: {1}")]
+    InvalidParameter(String, String),
+    #[error("Failed to parse font at line {0}: {1}")]
+    CannotParseFont(usize, &'static str),
+    #[error("Invalid marquee speed {0}, one loop would last too long")]
+    InvalidSpeed(f32),
+    #[error("Marquee text and gap must span between 1 and {} keys", i32::MAX)]
+    InvalidMarqueePeriod,
+    #[error(transparent)]
+    PngError(#[from] png::DecodingError),
+    #[error(transparent)]
+    GifError(#[from] gif::DecodingError),
+    #[error(transparent)]
+    PngEncodingError(#[from] png::EncodingError),
+    #[error(transparent)]
+    GifEncodingError(#[from] gif::EncodingError),
+    #[error("Failed to parse image: {0}")]
+    CannotParseImage(&'static str),
+    #[error("{0:?} has no polychromatic graphic")]
+    NoMapGraphic(Device),
+    #[error(transparent)]
+    SvgError(#[from] roxmltree::Error),
+    #[error("Failed to parse SVG: {0}")]
+    CannotParseSvg(&'static str),
+    #[error("Effect has no frames")]
+    NoFrames,
+    #[error("Preview of {0}x{1} pixels is too large")]
+    PreviewTooLarge(u64, u64),
+    #[error("Could not find the config directory, neither XDG_CONFIG_HOME or HOME are set")]
+    NoConfigDirectory,
+    #[error("Invalid effect name {0:?}")]
+    InvalidEffectName(String),
+    #[error("Effect {0:?} is not installed")]
+    EffectNotInstalled(String),
+    #[error("Effect {0:?} is already installed")]
+    EffectAlreadyInstalled(String),
 }
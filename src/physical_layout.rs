@@ -0,0 +1,202 @@
+//! Physical key positions, for effects that should look right on the real keyboard
+
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use crate::{
+    Color, Device, DeviceMap, Effect, EffectMatrix, FrameTime, KeyboardLayout, PolychromaticError,
+};
+
+/// Where polychromatic installs its device graphics
+pub const DEVICE_MAPS_DIR: &str = "/usr/share/polychromatic/devicemaps";
+
+/// Distance between the centers of neighbouring keys on most keyboards, in millimetres
+pub const KEY_PITCH: f32 = 19.05;
+
+/// Center of each key with an LED in millimetres, from the top left of the device
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicalLayout {
+    centers: BTreeMap<(u32, u32), (f32, f32)>,
+}
+
+impl PhysicalLayout {
+    /// Every cell of a `width` by `height` matrix as a key, [`KEY_PITCH`] apart
+    pub fn grid(width: u32, height: u32) -> Self {
+        let centers = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let center = ((x as f32 + 0.5) * KEY_PITCH, (y as f32 + 0.5) * KEY_PITCH);
+                ((x, y), center)
+            })
+            .collect();
+        Self { centers }
+    }
+
+    /// Key centers from a device graphic, cells without a key shape have no LED
+    pub fn from_device_map(map: &DeviceMap) -> Self {
+        let scale = map.millimetres_per_unit();
+        let centers = map
+            .geometry()
+            .map(|(key, geometry)| (key, geometry.scaled(scale).center()))
+            .collect();
+        Self { centers }
+    }
+
+    /// Layout from the device's graphic in `dir`, see [`Device::map_graphic`]
+    pub fn load_map_graphic<P: AsRef<Path>>(
+        device: &Device,
+        layout: KeyboardLayout,
+        dir: P,
+    ) -> Result<Self, PolychromaticError> {
+        let graphic = device
+            .map_graphic(layout)
+            .ok_or(PolychromaticError::NoMapGraphic(*device))?;
+        let map = DeviceMap::load(dir.as_ref().join(graphic))?;
+        Ok(Self::from_device_map(&map))
+    }
+
+    /// Layout from the device's graphic in `dir`, usually [`DEVICE_MAPS_DIR`], or a grid of the
+    /// device's matrix when there is no graphic. Graphics that can't be read or parsed are errors.
+    pub fn for_device<P: AsRef<Path>>(
+        device: &Device,
+        layout: KeyboardLayout,
+        dir: P,
+    ) -> Result<Self, PolychromaticError> {
+        match Self::load_map_graphic(device, layout, dir) {
+            Err(PolychromaticError::NoMapGraphic(_)) => {}
+            Err(PolychromaticError::IoError(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+            }
+            result => return result,
+        }
+        let (width, height) = device
+            .matrix()
+            .ok_or(PolychromaticError::DeviceUnsupportedEffects(*device))?;
+        Ok(Self::grid(width, height))
+    }
+
+    pub fn center(&self, x: u32, y: u32) -> Option<(f32, f32)> {
+        self.centers.get(&(x, y)).copied()
+    }
+
+    /// Center of every key in matrix order
+    pub fn iter(&self) -> impl Iterator<Item = ((u32, u32), (f32, f32))> + '_ {
+        self.centers.iter().map(|(key, center)| (*key, *center))
+    }
+
+    /// Smallest and largest key center, `None` without any keys
+    pub fn bounds(&self) -> Option<((f32, f32), (f32, f32))> {
+        self.centers.values().fold(None, |bounds, &(x, y)| {
+            Some(match bounds {
+                None => ((x, y), (x, y)),
+                Some((min, max)) => (
+                    (f32::min(min.0, x), f32::min(min.1, y)),
+                    (f32::max(max.0, x), f32::max(max.1, y)),
+                ),
+            })
+        })
+    }
+}
+
+impl EffectMatrix {
+    /// Set each key with an LED in `layout` from its physical center, other keys are black
+    pub fn fill_physical<F: FnMut(f32, f32) -> Color>(
+        &mut self,
+        layout: &PhysicalLayout,
+        mut color: F,
+    ) {
+        self.values_mut().fill(Color::default());
+        for ((x, y), (px, py)) in layout.iter() {
+            self.set(color(px, py), x, y);
+        }
+    }
+}
+
+impl Effect {
+    /// Physical layout of the effect's device and layout from the graphics in `dir`, see
+    /// [`PhysicalLayout::for_device`]
+    pub fn physical_layout<P: AsRef<Path>>(
+        &self,
+        dir: P,
+    ) -> Result<PhysicalLayout, PolychromaticError> {
+        PhysicalLayout::for_device(self.device(), self.layout, dir)
+    }
+
+    /// Append frames lasting `duration`, calling `render` with the physical center in
+    /// millimetres of each key with an LED in `layout`
+    pub fn render_physical<F: FnMut(f32, f32, FrameTime) -> Color>(
+        &mut self,
+        duration: Duration,
+        layout: &PhysicalLayout,
+        mut render: F,
+    ) {
+        self.render(duration, |time, frame| {
+            frame.fill_physical(layout, |x, y| render(x, y, time));
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        Color, Device, DeviceMap, Effect, KEY_PITCH, Keyboard, KeyboardLayout, PhysicalLayout,
+    };
+
+    #[test]
+    fn test_layout() {
+        let grid = PhysicalLayout::grid(3, 2);
+        assert_eq!(grid.center(2, 1), Some((2.5 * KEY_PITCH, 1.5 * KEY_PITCH)));
+        assert_eq!(grid.center(3, 0), None);
+        assert_eq!(
+            grid.bounds(),
+            Some((
+                (0.5 * KEY_PITCH, 0.5 * KEY_PITCH),
+                (2.5 * KEY_PITCH, 1.5 * KEY_PITCH)
+            ))
+        );
+
+        let map = DeviceMap::parse(include_str!("../tests/fixtures/keyboard.svg")).unwrap();
+        let layout = PhysicalLayout::from_device_map(&map);
+        assert_eq!(layout.center(1, 1), Some((41.0, 30.0)));
+        assert_eq!(layout.iter().count(), 5);
+
+        let device = Device::Keyboard(Keyboard::RazerHuntsmanMini);
+        assert!(
+            PhysicalLayout::load_map_graphic(&device, KeyboardLayout::EnUS, "tests/fixtures")
+                .is_err()
+        );
+        assert_eq!(
+            PhysicalLayout::for_device(&device, KeyboardLayout::EnUS, "tests/fixtures").unwrap(),
+            PhysicalLayout::grid(15, 5)
+        );
+
+        // A broken graphic isn't silently replaced by the grid.
+        let dir =
+            std::env::temp_dir().join(format!("polychromatic-devicemaps-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let graphic = device.map_graphic(KeyboardLayout::EnUS).unwrap();
+        std::fs::write(dir.join(graphic), "<svg>").unwrap();
+        assert!(PhysicalLayout::for_device(&device, KeyboardLayout::EnUS, &dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_render_physical() {
+        let map = DeviceMap::parse(include_str!("../tests/fixtures/keyboard.svg")).unwrap();
+        let layout = PhysicalLayout::from_device_map(&map);
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.render_physical(Duration::from_secs(1), &layout, |x, _, _| {
+            Color::new(x / 60.0, 0.0, 0.0)
+        });
+
+        let frame = &effect.frames()[0];
+        // The space bar spans two columns, so it is further right than the key above it.
+        assert!(frame.get(1, 1).unwrap().r > frame.get(1, 0).unwrap().r);
+        assert_eq!(frame.get(2, 1), Some(&Color::default()));
+        assert_eq!(frame.get(0, 1), frame.get(0, 0));
+    }
+}
@@ -0,0 +1,275 @@
+//! Playing effects in a truecolor terminal
+
+use std::{fmt::Write, time::Duration};
+
+use crate::{Effect, EffectMatrix};
+
+/// Position and state of an effect being played
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playback {
+    frame: usize,
+    frame_count: usize,
+    fps: u32,
+    r#loop: bool,
+    paused: bool,
+}
+
+impl Playback {
+    pub fn new(effect: &Effect) -> Self {
+        Self {
+            frame: 0,
+            frame_count: effect.frames().len(),
+            fps: effect.fps(),
+            r#loop: effect.r#loop,
+            paused: false,
+        }
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        // Resuming an effect that ended plays it again.
+        if !self.paused && !self.r#loop && self.frame + 1 >= self.frame_count {
+            self.frame = 0;
+        }
+    }
+
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps as f64)
+    }
+
+    /// Advance to the next frame unless paused, effects that don't loop pause on their last frame
+    pub fn tick(&mut self) {
+        if self.paused || self.frame_count == 0 {
+            return;
+        }
+        if self.frame + 1 < self.frame_count {
+            self.frame += 1;
+        } else if self.r#loop {
+            self.frame = 0;
+        } else {
+            self.paused = true;
+        }
+    }
+
+    /// Pause and move `frames` forward or backward
+    pub fn step(&mut self, frames: isize) {
+        self.paused = true;
+        self.seek_frames(frames);
+    }
+
+    /// Move `seconds` forward or backward
+    pub fn seek(&mut self, seconds: f32) {
+        self.seek_frames((seconds * self.fps as f32).round() as isize);
+    }
+
+    /// Jump to `frame`, clamped to the last frame
+    pub fn seek_to(&mut self, frame: usize) {
+        self.frame = frame.min(self.frame_count.saturating_sub(1));
+    }
+
+    /// Moves wrap around when looping and stop at the first or last frame otherwise
+    fn seek_frames(&mut self, frames: isize) {
+        let count = self.frame_count as isize;
+        if count == 0 {
+            return;
+        }
+        let frame = self.frame as isize + frames;
+        self.frame = match self.r#loop {
+            true => frame.rem_euclid(count),
+            false => frame.clamp(0, count - 1),
+        } as usize;
+    }
+
+    /// Frame counter, time and state
+    pub fn status(&self) -> String {
+        let seconds = |frame: usize| frame as f32 / self.fps as f32;
+        format!(
+            "frame {}/{}  {:.2}s/{:.2}s  {} FPS{}{}",
+            self.frame + 1,
+            self.frame_count,
+            seconds(self.frame),
+            seconds(self.frame_count),
+            self.fps,
+            if self.r#loop { "  loop" } else { "" },
+            if self.paused { "  paused" } else { "" },
+        )
+    }
+}
+
+impl EffectMatrix {
+    /// Draw each key as two terminal cells with a 24-bit background color
+    pub fn to_ansi(&self) -> String {
+        let mut ansi = String::new();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let (r, g, b) = self.get(x, y).unwrap().to_quantized();
+                write!(ansi, "\x1b[48;2;{r};{g};{b}m  ").unwrap();
+            }
+            ansi.push_str("\x1b[0m\r\n");
+        }
+        ansi
+    }
+}
+
+#[cfg(feature = "terminal")]
+mod terminal {
+    use std::{
+        io::{Write, stdout},
+        time::Instant,
+    };
+
+    use crossterm::{
+        QueueableCommand, cursor,
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        style::Print,
+        terminal,
+    };
+
+    use crate::{Effect, Playback, PolychromaticError};
+
+    const HELP: &str = "space pause  , . step  left right seek 1s  home end jump  q quit";
+
+    /// Restores the terminal when playback ends, even on errors
+    struct Screen;
+
+    impl Screen {
+        fn enter() -> std::io::Result<Self> {
+            terminal::enable_raw_mode()?;
+            let mut stdout = stdout();
+            stdout.queue(terminal::EnterAlternateScreen)?;
+            stdout.queue(cursor::Hide)?;
+            stdout.flush()?;
+            Ok(Self)
+        }
+    }
+
+    impl Drop for Screen {
+        fn drop(&mut self) {
+            let mut stdout = stdout();
+            let _ = stdout.queue(cursor::Show);
+            let _ = stdout.queue(terminal::LeaveAlternateScreen);
+            let _ = stdout.flush();
+            let _ = terminal::disable_raw_mode();
+        }
+    }
+
+    impl Effect {
+        /// Play the effect in the terminal at its FPS until `q` or escape is pressed
+        pub fn play_in_terminal(&self) -> Result<(), PolychromaticError> {
+            if self.frames().is_empty() {
+                return Err(PolychromaticError::NoFrames);
+            }
+            let _screen = Screen::enter()?;
+            let mut stdout = stdout();
+            let mut playback = Playback::new(self);
+            let mut next_frame = Instant::now() + playback.frame_duration();
+
+            loop {
+                stdout.queue(cursor::MoveTo(0, 0))?;
+                stdout.queue(Print(self.frames()[playback.frame()].to_ansi()))?;
+                stdout.queue(terminal::Clear(terminal::ClearType::UntilNewLine))?;
+                stdout.queue(Print(format!("{}\r\n{HELP}", playback.status())))?;
+                stdout.queue(terminal::Clear(terminal::ClearType::UntilNewLine))?;
+                stdout.flush()?;
+
+                let timeout = next_frame.saturating_duration_since(Instant::now());
+                if !event::poll(timeout)? {
+                    playback.tick();
+                    next_frame += playback.frame_duration();
+                    // Skip ahead instead of rushing through frames after falling behind.
+                    if next_frame < Instant::now() {
+                        next_frame = Instant::now() + playback.frame_duration();
+                    }
+                    continue;
+                }
+
+                let Event::Key(KeyEvent {
+                    code,
+                    modifiers,
+                    kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                    ..
+                }) = event::read()?
+                else {
+                    continue;
+                };
+                match code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Char(' ') => {
+                        playback.toggle_pause();
+                        next_frame = Instant::now() + playback.frame_duration();
+                    }
+                    KeyCode::Char(',') => playback.step(-1),
+                    KeyCode::Char('.') => playback.step(1),
+                    KeyCode::Left => playback.seek(-1.0),
+                    KeyCode::Right => playback.seek(1.0),
+                    KeyCode::Home => playback.seek_to(0),
+                    KeyCode::End => playback.seek_to(usize::MAX),
+                    _ => {}
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{Color, Device, Effect, Keyboard, Playback};
+
+    fn effect(r#loop: bool) -> Effect {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.set_fps(10).unwrap();
+        effect.r#loop = r#loop;
+        effect.render_pixels(Duration::from_secs(2), |_, _, _| Color::new(1.0, 0.5, 0.0));
+        effect
+    }
+
+    #[test]
+    fn test_playback() {
+        let mut playback = Playback::new(&effect(true));
+        playback.seek(-0.5);
+        assert_eq!(playback.frame(), 15);
+        (0..5).for_each(|_| playback.tick());
+        assert_eq!(playback.frame(), 0);
+        playback.step(-1);
+        assert!(playback.is_paused());
+        playback.tick();
+        assert_eq!(playback.frame(), 19);
+        assert_eq!(
+            playback.status(),
+            "frame 20/20  1.90s/2.00s  10 FPS  loop  paused"
+        );
+
+        let mut playback = Playback::new(&effect(false));
+        playback.seek(-0.5);
+        assert_eq!(playback.frame(), 0);
+        playback.seek_to(usize::MAX);
+        playback.tick();
+        assert_eq!(playback.frame(), 19);
+        assert!(playback.is_paused());
+        playback.toggle_pause();
+        assert_eq!(playback.frame(), 0);
+    }
+
+    #[test]
+    fn test_ansi() {
+        let ansi = effect(true).frames()[0].to_ansi();
+        let row = "\x1b[48;2;255;127;0m  ".repeat(15) + "\x1b[0m\r\n";
+        assert_eq!(ansi, row.repeat(5));
+    }
+}
@@ -0,0 +1,279 @@
+//! Animated previews of effects
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{Color, Effect, EffectMatrix, PolychromaticError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreviewFormat {
+    #[default]
+    Gif,
+    /// Animated PNG, unlike GIF colors and frame timing are exact
+    Apng,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreviewOptions {
+    pub format: PreviewFormat,
+    /// Size of each key in pixels
+    pub cell_size: u32,
+    /// Pixels between keys and around the edge
+    pub gap: u32,
+    /// Shown in the gaps between keys
+    pub background: Color,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            format: PreviewFormat::default(),
+            cell_size: 16,
+            gap: 2,
+            background: Color::from_quantized(32, 32, 32),
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Image size in pixels for a matrix of `width` by `height` keys
+    pub fn image_size(&self, width: u32, height: u32) -> Result<(u32, u32), PolychromaticError> {
+        let size =
+            |keys: u32| keys as u64 * (self.cell_size as u64 + self.gap as u64) + self.gap as u64;
+        let (width, height) = (size(width), size(height));
+        match (u32::try_from(width), u32::try_from(height)) {
+            // Three bytes per pixel have to fit in memory as well.
+            (Ok(width), Ok(height))
+                if (width as usize).checked_mul(height as usize * 3).is_some() =>
+            {
+                Ok((width, height))
+            }
+            _ => Err(PolychromaticError::PreviewTooLarge(width, height)),
+        }
+    }
+
+    /// Draw `frame` as 8 bit RGB pixels
+    pub fn draw(&self, frame: &EffectMatrix) -> Result<Vec<u8>, PolychromaticError> {
+        let (width, height) = self.image_size(frame.width(), frame.height())?;
+        let (width, cell_size, gap) = (width as usize, self.cell_size as usize, self.gap as usize);
+        let mut pixels = rgb(&self.background).repeat(width * height as usize);
+        for (x, y, color) in frame.iter() {
+            let color = rgb(color);
+            let left = gap + x as usize * (cell_size + gap);
+            let top = gap + y as usize * (cell_size + gap);
+            for py in top..top + cell_size {
+                let start = (left + py * width) * 3;
+                let end = start + cell_size * 3;
+                pixels[start..end]
+                    .chunks_exact_mut(3)
+                    .for_each(|pixel| pixel.copy_from_slice(&color));
+            }
+        }
+        Ok(pixels)
+    }
+}
+
+fn rgb(color: &Color) -> [u8; 3] {
+    let (r, g, b) = color.to_quantized();
+    [r, g, b]
+}
+
+impl Effect {
+    /// Write an animation of the frames at the effect's FPS, looping if the effect does
+    pub fn write_preview<W: Write>(
+        &self,
+        writer: W,
+        options: PreviewOptions,
+    ) -> Result<(), PolychromaticError> {
+        if self.frames().is_empty() {
+            return Err(PolychromaticError::NoFrames);
+        }
+        match options.format {
+            PreviewFormat::Gif => self.write_gif(writer, options),
+            PreviewFormat::Apng => self.write_apng(writer, options),
+        }
+    }
+
+    /// Save a preview to `path`, see [`Effect::write_preview`]
+    pub fn export_preview<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: PreviewOptions,
+    ) -> Result<(), PolychromaticError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_preview(&mut writer, options)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn write_gif<W: Write>(
+        &self,
+        writer: W,
+        options: PreviewOptions,
+    ) -> Result<(), PolychromaticError> {
+        let (width, height) = options.image_size(self.width(), self.height())?;
+        // GIF sizes are 16 bit.
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(PolychromaticError::PreviewTooLarge(
+                width as u64,
+                height as u64,
+            ));
+        };
+        let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+        encoder.set_repeat(match self.r#loop {
+            true => gif::Repeat::Infinite,
+            false => gif::Repeat::Finite(0),
+        })?;
+
+        let fps = self.fps() as u64;
+        for (index, frame) in self.frames().iter().enumerate() {
+            let pixels = options.draw(frame)?;
+            // Keyboards have fewer keys than a GIF palette has colors, so colors are usually
+            // exact and only quantized when there are too many.
+            let mut palette = HashMap::new();
+            let indices = pixels
+                .chunks_exact(3)
+                .map(|pixel| {
+                    let next = palette.len();
+                    *palette
+                        .entry([pixel[0], pixel[1], pixel[2]])
+                        .or_insert(next) as u8
+                })
+                .collect::<Vec<_>>();
+            let mut gif_frame = if palette.len() <= 256 {
+                let mut colors = vec![0; palette.len() * 3];
+                for (color, index) in palette {
+                    colors[index * 3..index * 3 + 3].copy_from_slice(&color);
+                }
+                gif::Frame::from_palette_pixels(width, height, indices, colors, None)
+            } else {
+                gif::Frame::from_rgb_speed(width, height, &pixels, 10)
+            };
+            // GIF delays are in hundredths of a second, rounding the end of each frame keeps
+            // the total duration exact.
+            let end = |index: u64| (index * 100 + fps / 2) / fps;
+            gif_frame.delay = (end(index as u64 + 1) - end(index as u64)) as u16;
+            encoder.write_frame(&gif_frame)?;
+        }
+        Ok(())
+    }
+
+    fn write_apng<W: Write>(
+        &self,
+        writer: W,
+        options: PreviewOptions,
+    ) -> Result<(), PolychromaticError> {
+        let (width, height) = options.image_size(self.width(), self.height())?;
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let plays = if self.r#loop { 0 } else { 1 };
+        encoder.set_animated(self.frames().len() as u32, plays)?;
+        encoder.set_frame_delay(1, self.fps() as u16)?;
+        let mut writer = encoder.write_header()?;
+        for frame in self.frames() {
+            writer.write_image_data(&options.draw(frame)?)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{Color, Device, Effect, ImageFrame, Keyboard, PreviewFormat, PreviewOptions, Rgba};
+
+    fn effect() -> Effect {
+        let mut effect = Effect::new(
+            Device::Keyboard(Keyboard::RazerHuntsmanMini),
+            "effects/pride.png",
+        )
+        .unwrap();
+        effect.set_fps(30).unwrap();
+        effect.render_pixels(Duration::from_millis(100), |x, _, time| {
+            if x == time.index as u32 {
+                Color::new(1.0, 0.0, 0.0)
+            } else {
+                Color::default()
+            }
+        });
+        effect
+    }
+
+    #[test]
+    fn test_gif() {
+        let effect = effect();
+        let options = PreviewOptions {
+            cell_size: 2,
+            gap: 1,
+            background: Color::new(0.0, 0.0, 1.0),
+            ..Default::default()
+        };
+        let mut gif = Vec::new();
+        effect.write_preview(&mut gif, options).unwrap();
+
+        let frames = ImageFrame::decode(&gif).unwrap();
+        let delays = frames
+            .iter()
+            .map(|frame| frame.delay.as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [30, 40, 30]);
+        let image = &frames[1].image;
+        assert_eq!(
+            (image.width(), image.height()),
+            options.image_size(effect.width(), effect.height()).unwrap()
+        );
+
+        // 15 keys of 4370 pixels don't fit in a GIF.
+        let large = PreviewOptions {
+            cell_size: 4370,
+            ..options
+        };
+        assert!(large.image_size(effect.width(), effect.height()).is_ok());
+        assert!(effect.write_preview(Vec::new(), large).is_err());
+        assert!(large.image_size(u32::MAX, 1).is_err());
+        let blue = Rgba::new(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(image.get(0, 0), Some(&blue));
+        assert_eq!(image.get(1, 1), Some(&Rgba::new(0.0, 0.0, 0.0, 1.0)));
+        assert_eq!(image.get(4, 2), Some(&Rgba::new(1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(image.get(6, 2), Some(&blue));
+    }
+
+    #[test]
+    fn test_apng() {
+        let mut effect = effect();
+        effect.r#loop = false;
+        let mut apng = Vec::new();
+        effect
+            .write_preview(
+                &mut apng,
+                PreviewOptions {
+                    format: PreviewFormat::Apng,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let reader = png::Decoder::new(apng.as_slice()).read_info().unwrap();
+        let animation = reader.info().animation_control.unwrap();
+        assert_eq!((animation.num_frames, animation.num_plays), (3, 1));
+        let control = reader.info().frame_control.unwrap();
+        assert_eq!((control.delay_num, control.delay_den), (1, 30));
+
+        assert!(
+            Effect::new(
+                Device::Keyboard(Keyboard::RazerHuntsmanMini),
+                "effects/pride.png"
+            )
+            .unwrap()
+            .write_preview(Vec::new(), PreviewOptions::default())
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+
+use crate::{PolychromaticError, defs, device::Device};
+
+pub use defs::{Parameter, ParameterKind};
+
+/// Python source of a scripted effect
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptSource {
+    Path(PathBuf),
+    Code(String),
+}
+
+/// Python script polychromatic runs, with parameters the user can edit in the app
+#[derive(Debug)]
+pub struct ScriptedEffect {
+    pub name: String,
+    pub author: String,
+    pub icon: PathBuf,
+    pub summary: String,
+    pub designed_for: Vec<Device>,
+    pub script: ScriptSource,
+    /// Write indented JSON
+    pub pretty: bool,
+    parameters: Vec<Parameter>,
+}
+
+impl ScriptedEffect {
+    pub fn new<P: AsRef<Path>>(script: ScriptSource, icon: P) -> Self {
+        Self {
+            name: "Unnamed".to_owned(),
+            author: String::new(),
+            icon: icon.as_ref().to_path_buf(),
+            summary: String::new(),
+            designed_for: Vec::new(),
+            script,
+            pretty: false,
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn parameters(&self) -> &[Parameter] {
+        &self.parameters
+    }
+
+    /// Add a parameter, checking its default value is valid
+    pub fn add_parameter<V: Into<String>, L: Into<String>>(
+        &mut self,
+        var: V,
+        label: L,
+        kind: ParameterKind,
+    ) -> Result<&mut Self, PolychromaticError> {
+        let var = var.into();
+        let invalid =
+            |reason: &str| PolychromaticError::InvalidParameter(var.clone(), reason.to_owned());
+        match &kind {
+            ParameterKind::Int { default, min, max } if !(min..=max).contains(&default) => {
+                return Err(invalid("Default value out of range"));
+            }
+            ParameterKind::Float {
+                default, min, max, ..
+            } if !(min..=max).contains(&default) => {
+                return Err(invalid("Default value out of range"));
+            }
+            ParameterKind::Choice { default, options } if !options.contains(default) => {
+                return Err(invalid("Default value isn't an option"));
+            }
+            _ => {}
+        }
+        if self.parameters.iter().any(|parameter| parameter.var == var) {
+            return Err(invalid("Parameter already exists"));
+        }
+        self.parameters.push(Parameter {
+            var,
+            label: label.into(),
+            kind,
+        });
+        Ok(self)
+    }
+
+    pub fn to_effect_json(&self) -> Result<String, PolychromaticError> {
+        Ok(defs::to_json(
+            &defs::ScriptedEffect {
+                name: self.name.clone(),
+                author: self.author.clone(),
+                icon: self.icon.clone().canonicalize()?,
+                summary: self.summary.clone(),
+                r#type: 2,
+                designed_for: self
+                    .designed_for
+                    .iter()
+                    .map(|device| device.to_string())
+                    .collect(),
+                parameters: self.parameters.clone(),
+                save_format: 8,
+                revision: 1,
+            },
+            self.pretty,
+        )?)
+    }
+
+    /// Write the manifest to `path` and the script next to it with a `.py` extension
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PolychromaticError> {
+        let path = path.as_ref();
+        let script = match &self.script {
+            ScriptSource::Path(script_path) => std::fs::read_to_string(script_path)?,
+            ScriptSource::Code(code) => code.clone(),
+        };
+        let str = self.to_effect_json()?;
+        std::fs::write(path, &str)?;
+        std::fs::write(path.with_extension("py"), &script)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Color, Device, Keyboard, ParameterKind, PolychromaticError, ScriptSource, ScriptedEffect,
+    };
+
+    #[test]
+    fn test_scripted_effect() {
+        let mut effect = ScriptedEffect::new(
+            ScriptSource::Code("print('Hello')\n".to_owned()),
+            "effects/pride.png",
+        );
+        effect
+            .designed_for
+            .push(Device::Keyboard(Keyboard::RazerHuntsmanMini));
+        effect
+            .add_parameter(
+                "color",
+                "Color",
+                ParameterKind::Color {
+                    default: Color::from_quantized(255, 0, 0),
+                },
+            )
+            .unwrap()
+            .add_parameter(
+                "speed",
+                "Speed",
+                ParameterKind::Int {
+                    default: 5,
+                    min: 1,
+                    max: 10,
+                },
+            )
+            .unwrap();
+        assert!(matches!(
+            effect.add_parameter("speed", "Speed", ParameterKind::Bool { default: true }),
+            Err(PolychromaticError::InvalidParameter(..))
+        ));
+        assert!(matches!(
+            effect.add_parameter(
+                "size",
+                "Size",
+                ParameterKind::Float {
+                    default: 2.0,
+                    min: 0.0,
+                    max: 1.0,
+                    step: 0.1
+                }
+            ),
+            Err(PolychromaticError::InvalidParameter(..))
+        ));
+
+        let dir = std::env::temp_dir().join(format!("polychromatic-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        effect.save(dir.join("Scripted.json")).unwrap();
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("Scripted.json")).unwrap())
+                .unwrap();
+        assert_eq!(manifest["type"], 2);
+        assert_eq!(manifest["designed_for"][0], "Razer Huntsman Mini");
+        assert_eq!(manifest["parameters"][1]["max"], 10);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("Scripted.py")).unwrap(),
+            "print('Hello')\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,282 @@
+//! Just enough SVG geometry to find where the shapes of a device graphic are
+
+use roxmltree::Node;
+
+/// 2D affine transform `[a, b, c, d, e, f]` as in the SVG `matrix()` transform
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Transform([f32; 6]);
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self([1.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+    }
+}
+
+impl Transform {
+    /// `other` applied first, then `self`
+    fn then(&self, other: &Transform) -> Transform {
+        let [a, b, c, d, e, f] = self.0;
+        let [oa, ob, oc, od, oe, of] = other.0;
+        Transform([
+            a * oa + c * ob,
+            b * oa + d * ob,
+            a * oc + c * od,
+            b * oc + d * od,
+            a * oe + c * of + e,
+            b * oe + d * of + f,
+        ])
+    }
+
+    pub(crate) fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    /// Parse a `transform` attribute, unknown functions are ignored
+    pub(crate) fn parse(str: &str) -> Transform {
+        let mut transform = Transform::default();
+        for function in str.split_inclusive(')') {
+            let Some((name, args)) = function.split_once('(') else {
+                continue;
+            };
+            let args = numbers(args.trim_end_matches(')'));
+            let next = match (
+                name.trim_matches(|char: char| char.is_whitespace() || char == ','),
+                args.as_slice(),
+            ) {
+                ("matrix", &[a, b, c, d, e, f]) => Transform([a, b, c, d, e, f]),
+                ("translate", &[x]) => Transform([1.0, 0.0, 0.0, 1.0, x, 0.0]),
+                ("translate", &[x, y]) => Transform([1.0, 0.0, 0.0, 1.0, x, y]),
+                ("scale", &[s]) => Transform([s, 0.0, 0.0, s, 0.0, 0.0]),
+                ("scale", &[x, y]) => Transform([x, 0.0, 0.0, y, 0.0, 0.0]),
+                ("rotate", &[angle, ..]) => {
+                    let (sin, cos) = angle.to_radians().sin_cos();
+                    let rotate = Transform([cos, sin, -sin, cos, 0.0, 0.0]);
+                    match args[1..] {
+                        [x, y] => Transform([1.0, 0.0, 0.0, 1.0, x, y])
+                            .then(&rotate)
+                            .then(&Transform([1.0, 0.0, 0.0, 1.0, -x, -y])),
+                        _ => rotate,
+                    }
+                }
+                ("skewX", &[angle]) => {
+                    Transform([1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0])
+                }
+                ("skewY", &[angle]) => {
+                    Transform([1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0])
+                }
+                _ => continue,
+            };
+            transform = transform.then(&next);
+        }
+        transform
+    }
+
+    /// Combined transform of `node` and all of its ancestors
+    pub(crate) fn of(node: Node) -> Transform {
+        node.ancestors()
+            .filter_map(|node| node.attribute("transform"))
+            .fold(Transform::default(), |transform, attribute| {
+                Transform::parse(attribute).then(&transform)
+            })
+    }
+}
+
+/// Axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Bounds {
+    pub(crate) min: (f32, f32),
+    pub(crate) max: (f32, f32),
+}
+
+impl Bounds {
+    pub(crate) fn of_points<I: IntoIterator<Item = (f32, f32)>>(points: I) -> Option<Bounds> {
+        points.into_iter().fold(None, |bounds, point| {
+            Some(match bounds {
+                None => Bounds {
+                    min: point,
+                    max: point,
+                },
+                Some(Bounds { min, max }) => Bounds {
+                    min: (min.0.min(point.0), min.1.min(point.1)),
+                    max: (max.0.max(point.0), max.1.max(point.1)),
+                },
+            })
+        })
+    }
+
+    pub(crate) fn union(&self, other: &Bounds) -> Bounds {
+        Bounds::of_points([self.min, self.max, other.min, other.max]).unwrap()
+    }
+
+    fn corners(&self) -> [(f32, f32); 4] {
+        [
+            self.min,
+            (self.max.0, self.min.1),
+            self.max,
+            (self.min.0, self.max.1),
+        ]
+    }
+}
+
+/// Leading number of a length like `18px`, units are ignored
+pub(crate) fn length(str: &str) -> Option<f32> {
+    numbers(str).first().copied()
+}
+
+/// Numbers in a list separated by whitespace, commas or nothing at all, as in `1-2.5.5`
+fn numbers(str: &str) -> Vec<f32> {
+    let mut numbers = Vec::new();
+    let bytes = str.as_bytes();
+    let mut position = 0;
+    while position < bytes.len() {
+        let start = position;
+        if matches!(bytes[position], b'+' | b'-') {
+            position += 1;
+        }
+        let mut dot = false;
+        let mut exponent = false;
+        while let Some(&byte) = bytes.get(position) {
+            match byte {
+                b'0'..=b'9' => {}
+                b'.' if !dot && !exponent => dot = true,
+                b'e' | b'E' if !exponent && position > start => {
+                    exponent = true;
+                    if matches!(bytes.get(position + 1), Some(b'+' | b'-')) {
+                        position += 1;
+                    }
+                }
+                _ => break,
+            }
+            position += 1;
+        }
+        match str[start..position].parse() {
+            Ok(number) => numbers.push(number),
+            // Skip separators and anything else that isn't a number, a character at a time.
+            Err(_) => position = start + str[start..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    numbers
+}
+
+/// Points of path `d` data that bound the path, curves are bounded by their control points and
+/// arcs only by their end points
+fn path_points(d: &str) -> Vec<(f32, f32)> {
+    let mut points = Vec::new();
+    let mut current = (0.0, 0.0);
+    let mut start = (0.0, 0.0);
+    let commands = d
+        .match_indices(|char: char| char.is_ascii_alphabetic() && !matches!(char, 'e' | 'E'))
+        .map(|(index, command)| (index, command.chars().next().unwrap()))
+        .collect::<Vec<_>>();
+    for (i, &(index, command)) in commands.iter().enumerate() {
+        let end = commands.get(i + 1).map_or(d.len(), |(index, _)| *index);
+        let args = numbers(&d[index + 1..end]);
+        let relative = command.is_ascii_lowercase();
+        let offset = |current: (f32, f32)| if relative { current } else { (0.0, 0.0) };
+        let (stride, command) = match command.to_ascii_uppercase() {
+            'Z' => {
+                current = start;
+                continue;
+            }
+            'H' | 'V' => (1, command.to_ascii_uppercase()),
+            'M' | 'L' | 'T' => (2, command.to_ascii_uppercase()),
+            'S' | 'Q' => (4, command.to_ascii_uppercase()),
+            'C' => (6, 'C'),
+            'A' => (7, 'A'),
+            _ => continue,
+        };
+        for (n, args) in args.chunks_exact(stride).enumerate() {
+            let base = offset(current);
+            let point = |x: f32, y: f32| (base.0 + x, base.1 + y);
+            current = match (command, args) {
+                ('H', &[x]) => (base.0 + x, current.1),
+                ('V', &[y]) => (current.0, base.1 + y),
+                ('M' | 'L' | 'T', &[x, y]) => point(x, y),
+                ('S' | 'Q', &[x1, y1, x, y]) => {
+                    points.push(point(x1, y1));
+                    point(x, y)
+                }
+                ('C', &[x1, y1, x2, y2, x, y]) => {
+                    points.push(point(x1, y1));
+                    points.push(point(x2, y2));
+                    point(x, y)
+                }
+                ('A', &[.., x, y]) => point(x, y),
+                _ => unreachable!("arguments are chunked by stride"),
+            };
+            if command == 'M' && n == 0 {
+                start = current;
+            }
+            points.push(current);
+        }
+    }
+    points
+}
+
+/// Bounds of a basic shape or path element in its own coordinates
+fn shape_bounds(node: Node) -> Option<Bounds> {
+    let number = |name: &str| node.attribute(name).and_then(length).unwrap_or(0.0);
+    match node.tag_name().name() {
+        "rect" | "image" | "use" => {
+            let (x, y) = (number("x"), number("y"));
+            Bounds::of_points([(x, y), (x + number("width"), y + number("height"))])
+        }
+        "circle" => {
+            let (x, y, r) = (number("cx"), number("cy"), number("r"));
+            Bounds::of_points([(x - r, y - r), (x + r, y + r)])
+        }
+        "ellipse" => {
+            let (x, y) = (number("cx"), number("cy"));
+            let (rx, ry) = (number("rx"), number("ry"));
+            Bounds::of_points([(x - rx, y - ry), (x + rx, y + ry)])
+        }
+        "line" => Bounds::of_points([(number("x1"), number("y1")), (number("x2"), number("y2"))]),
+        "polygon" | "polyline" => {
+            let points = numbers(node.attribute("points")?);
+            Bounds::of_points(points.chunks_exact(2).map(|point| (point[0], point[1])))
+        }
+        "path" => Bounds::of_points(path_points(node.attribute("d")?)),
+        _ => None,
+    }
+}
+
+/// Bounds in root coordinates of `node` and the shapes inside it, text is left out
+pub(crate) fn bounds(node: Node) -> Option<Bounds> {
+    node.descendants()
+        .filter(|node| node.is_element())
+        .filter(|node| !node.ancestors().any(|node| node.has_tag_name("text")))
+        .filter_map(|node| {
+            let bounds = shape_bounds(node)?;
+            let transform = Transform::of(node);
+            Bounds::of_points(bounds.corners().map(|corner| transform.apply(corner)))
+        })
+        .reduce(|a, b| a.union(&b))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::svg::{Transform, numbers, path_points};
+
+    #[test]
+    fn test_svg() {
+        assert_eq!(numbers("1-2.5.5e1,3 -4"), [1.0, -2.5, 5.0, 3.0, -4.0]);
+        assert_eq!(numbers("é1\u{a0}2€-3"), [1.0, 2.0, -3.0]);
+        assert_eq!(
+            path_points("M1 1h2v2H0z m1,1 l1 1"),
+            [
+                (1.0, 1.0),
+                (3.0, 1.0),
+                (3.0, 3.0),
+                (0.0, 3.0),
+                (2.0, 2.0),
+                (3.0, 3.0)
+            ]
+        );
+
+        let transform = Transform::parse("translate(10) scale(2)");
+        assert_eq!(transform.apply((1.0, 1.0)), (12.0, 2.0));
+        let (x, y) = Transform::parse("rotate(90 1 1)").apply((2.0, 1.0));
+        assert!((x - 1.0).abs() < 1e-6 && (y - 2.0).abs() < 1e-6);
+    }
+}
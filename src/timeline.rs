@@ -0,0 +1,212 @@
+//! Keyframed properties sampled while rendering an [`Effect`](crate::Effect)
+
+use std::collections::BTreeMap;
+
+use crate::{Color, Easing, Interpolate};
+
+#[derive(Debug, Clone)]
+pub struct Keyframe<T> {
+    /// Seconds since the start of the animation
+    pub time: f32,
+    pub value: T,
+    /// Easing of the segment starting at this keyframe
+    pub easing: Easing,
+}
+
+/// Keyframes of a single property
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Self {
+            keyframes: Vec::new(),
+        }
+    }
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a keyframe, replacing any keyframe at the same time
+    pub fn insert(&mut self, time: f32, value: T, easing: Easing) -> &mut Self {
+        let keyframe = Keyframe {
+            time,
+            value,
+            easing,
+        };
+        match self
+            .keyframes
+            .binary_search_by(|keyframe| keyframe.time.total_cmp(&time))
+        {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+        self
+    }
+
+    /// Builder version of [`Track::insert`]
+    pub fn key(mut self, time: f32, value: T, easing: Easing) -> Self {
+        self.insert(time, value, easing);
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// Time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Value at `time`, holding the first and last keyframes outside of the track
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        match (next.checked_sub(1), self.keyframes.get(next)) {
+            (None, next) => next.map(|keyframe| keyframe.value.clone()),
+            (Some(previous), None) => Some(self.keyframes[previous].value.clone()),
+            (Some(previous), Some(next)) => {
+                let previous = &self.keyframes[previous];
+                let t = (time - previous.time) / (next.time - previous.time);
+                Some(
+                    previous
+                        .value
+                        .interpolate(&next.value, previous.easing.apply(t)),
+                )
+            }
+        }
+    }
+}
+
+/// Types that can be stored in a [`Timeline`]
+pub trait TimelineValue: Interpolate + Sized {
+    fn tracks(timeline: &Timeline) -> &BTreeMap<String, Track<Self>>;
+    fn tracks_mut(timeline: &mut Timeline) -> &mut BTreeMap<String, Track<Self>>;
+}
+
+impl TimelineValue for f32 {
+    fn tracks(timeline: &Timeline) -> &BTreeMap<String, Track<Self>> {
+        &timeline.floats
+    }
+
+    fn tracks_mut(timeline: &mut Timeline) -> &mut BTreeMap<String, Track<Self>> {
+        &mut timeline.floats
+    }
+}
+
+impl TimelineValue for (f32, f32) {
+    fn tracks(timeline: &Timeline) -> &BTreeMap<String, Track<Self>> {
+        &timeline.positions
+    }
+
+    fn tracks_mut(timeline: &mut Timeline) -> &mut BTreeMap<String, Track<Self>> {
+        &mut timeline.positions
+    }
+}
+
+impl TimelineValue for Color {
+    fn tracks(timeline: &Timeline) -> &BTreeMap<String, Track<Self>> {
+        &timeline.colors
+    }
+
+    fn tracks_mut(timeline: &mut Timeline) -> &mut BTreeMap<String, Track<Self>> {
+        &mut timeline.colors
+    }
+}
+
+/// Named tracks of numbers, positions and colors
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    floats: BTreeMap<String, Track<f32>>,
+    positions: BTreeMap<String, Track<(f32, f32)>>,
+    colors: BTreeMap<String, Track<Color>>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a track, replacing any track of the same type and name
+    pub fn insert<T: TimelineValue, S: Into<String>>(
+        &mut self,
+        name: S,
+        track: Track<T>,
+    ) -> &mut Self {
+        T::tracks_mut(self).insert(name.into(), track);
+        self
+    }
+
+    pub fn track<T: TimelineValue>(&self, name: &str) -> Option<&Track<T>> {
+        T::tracks(self).get(name)
+    }
+
+    pub fn track_mut<T: TimelineValue>(&mut self, name: &str) -> Option<&mut Track<T>> {
+        T::tracks_mut(self).get_mut(name)
+    }
+
+    /// Value of the track `name` at `time`
+    pub fn sample<T: TimelineValue>(&self, name: &str, time: f32) -> Option<T> {
+        self.track::<T>(name)?.sample(time)
+    }
+
+    /// Time of the last keyframe of any track
+    pub fn duration(&self) -> f32 {
+        self.floats
+            .values()
+            .map(Track::duration)
+            .chain(self.positions.values().map(Track::duration))
+            .chain(self.colors.values().map(Track::duration))
+            .fold(0.0, f32::max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Color, Easing, Timeline, Track};
+
+    #[test]
+    fn test_track() {
+        let track = Track::new()
+            .key(1.0, 10.0, Easing::Linear)
+            .key(0.0, 0.0, Easing::Custom(|t| t * t))
+            .key(2.0, 20.0, Easing::Hold);
+        assert_eq!(track.sample(-1.0), Some(0.0));
+        assert_eq!(track.sample(0.5), Some(2.5));
+        assert_eq!(track.sample(1.5), Some(15.0));
+        assert_eq!(track.sample(3.0), Some(20.0));
+        assert_eq!(Track::<f32>::new().sample(0.0), None);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let mut timeline = Timeline::new();
+        timeline
+            .insert(
+                "brightness",
+                Track::new()
+                    .key(0.0, 0.0, Easing::Hold)
+                    .key(0.5, 1.0, Easing::Linear),
+            )
+            .insert(
+                "color",
+                Track::new()
+                    .key(0.0, Color::new(1.0, 0.0, 0.0), Easing::Linear)
+                    .key(2.0, Color::new(0.0, 0.0, 1.0), Easing::Linear),
+            );
+        assert_eq!(timeline.duration(), 2.0);
+        assert_eq!(timeline.sample::<f32>("brightness", 0.25), Some(0.0));
+        assert_eq!(
+            timeline.sample::<Color>("color", 1.0),
+            Some(Color::new(0.5, 0.0, 0.5))
+        );
+        assert_eq!(timeline.sample::<Color>("brightness", 1.0), None);
+    }
+}